@@ -9,7 +9,10 @@ use midi_daw_types::{
     AddNoteBody, ChangeLenByBody, GetSequenceQuery, MidiChannel, MsgFromServer, RenameSequenceBody,
     RmNoteBody, Sequence, SetChannelBody, SetDevBody,
 };
-use std::sync::{Arc, RwLock};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::*;
@@ -130,7 +133,8 @@ fn main() {
         .launch(App);
 }
 
-async fn sync_pulse_reader(tx: UnboundedSender<usize>) -> () {
+/// sends the step each playing sequence is on, (None when the sequencer resets).
+async fn sync_pulse_reader(tx: UnboundedSender<Option<(String, usize)>>) -> () {
     loop {
         let (socket, response) = match connect_async(format!("ws://{BASE_URL}/message-bus")).await {
             Ok(val) => val,
@@ -170,20 +174,19 @@ async fn sync_pulse_reader(tx: UnboundedSender<usize>) -> () {
                     Ok(Message::Binary(msg)) => {
                         if let Ok(msg) = MsgFromServer::from_bytes(&msg.to_vec()) {
                             match msg {
-                                MsgFromServer::Step {
-                                    pulse_count: _,
+                                MsgFromServer::SequenceStep {
+                                    sequence_name,
                                     step_n,
-                                    step_type: _,
-                                    bpq: _,
+                                    step_len: _,
                                 } => {
-                                    if let Err(e) = tx.send(step_n) {
+                                    if let Err(e) = tx.send(Some((sequence_name, step_n))) {
                                         error!("counter send error: {e}");
                                     } else {
                                         // info!("counter is {step_n}");
                                     }
                                 }
                                 MsgFromServer::SyncPulseReset() => {
-                                    if let Err(e) = tx.send(0) {
+                                    if let Err(e) = tx.send(None) {
                                         error!("counter send error: {e}");
                                     }
                                 }
@@ -1031,13 +1034,18 @@ fn MiddleCol(
     // counter: Signal<f64>,
 ) -> Element {
     // let is_drum_track = use_signal(|| sections.read()[displaying()].is_drum);
-    let mut counter = use_signal(|| 0);
+    // the step each playing sequence is on
+    let mut seq_steps = use_signal(|| HashMap::<String, usize>::new());
     let (tx, mut sync_pulse) = unbounded_channel();
     let _thread_jh = spawn(async move { sync_pulse_reader(tx).await });
     let _recv_jh = spawn(async move {
         loop {
-            if let Some(c) = sync_pulse.recv().await {
-                counter.set(c);
+            match sync_pulse.recv().await {
+                Some(Some((name, step_n))) => {
+                    seq_steps.write().insert(name, step_n);
+                }
+                Some(None) => seq_steps.write().clear(),
+                None => {}
             }
         }
     });
@@ -1046,7 +1054,7 @@ fn MiddleCol(
         div {
             id: "middle-main",
             if middle_view() == MiddleColView::Section && !sections.read().read().unwrap()[*displaying().read().unwrap()].is_drum {
-                SectionDisplay { middle_view, sections, displaying, edit_cell, choosing_device, counter }
+                SectionDisplay { middle_view, sections, displaying, edit_cell, choosing_device, seq_steps }
             } else if middle_view() == MiddleColView::Section && sections.read().read().unwrap()[*displaying().read().unwrap()].is_drum {
                 DrumSectionDisplay { middle_view, sections, displaying }
             } else if middle_view() == MiddleColView::Pattern {}
@@ -1169,7 +1177,7 @@ fn SectionDisplay(
     displaying: Signal<Arc<RwLock<usize>>>,
     edit_cell: Signal<Option<(usize, Colums)>>,
     choosing_device: Signal<bool>,
-    counter: Signal<usize>,
+    seq_steps: Signal<HashMap<String, usize>>,
 ) -> Element {
     // info!("regular section view");
 
@@ -1208,7 +1216,7 @@ fn SectionDisplay(
                                 class: {
                                     let mut class = "lin-number".into();
 
-                                    if counter() % (sections().read().unwrap()[*displaying().read().unwrap()].steps.len()) == i {
+                                    if seq_steps().get(&sections().read().unwrap()[*displaying().read().unwrap()].name) == Some(&i) {
                                         // info!("i is {i}");
 
                                         class = format!("{class} text-red");
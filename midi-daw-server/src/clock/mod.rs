@@ -0,0 +1,253 @@
+use midi_daw_types::NoteDuration;
+use std::time::{Duration, Instant};
+
+/// a source of time. time is measured from when the clock started.
+pub trait Clock {
+    /// the time since the clock started.
+    fn now(&self) -> Duration;
+}
+
+/// the real, monotonic, system clock. copies share the same start instant.
+#[derive(Clone, Copy, Debug)]
+pub struct MonotonicClock {
+    start: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// the length of one sync pulse in seconds.
+pub fn pulse_len(tempo: f64, bpq: f64) -> f64 {
+    (60.0 / tempo) / bpq
+}
+
+/// the number of sync pulses a note or rest lasts. at least one, so notes end after they start.
+pub fn note_pulses(duration: NoteDuration, bpq: f64) -> u64 {
+    (duration.quarter_notes() * bpq).round().max(1.0) as u64
+}
+
+/// schedules sync pulses at absolute deadlines. every deadline is computed from the point where
+/// the current tempo took effect, so lateness in one pulse never carries over to the next.
+#[derive(Clone, Debug)]
+pub struct PulseScheduler<C: Clock = MonotonicClock> {
+    clock: C,
+    /// the pulse the current tempo took effect on
+    anchor_pulse: u64,
+    /// the time the current tempo took effect
+    anchor_time: Duration,
+    /// the length of a pulse in seconds at the current tempo
+    pulse_len: f64,
+    tempo: f64,
+    bpq: f64,
+    /// the next pulse to happen
+    pulse: u64,
+}
+
+impl<C: Clock> PulseScheduler<C> {
+    /// pulses are lined up with the start of the clock, so schedulers sharing a clock & tempo
+    /// pulse together. the first pulse is the next one on that grid.
+    pub fn new(clock: C, tempo: f64, bpq: f64) -> Self {
+        let pulse_len = pulse_len(tempo, bpq);
+        let pulse = (clock.now().as_secs_f64() / pulse_len).ceil() as u64;
+
+        Self {
+            clock,
+            anchor_pulse: 0,
+            anchor_time: Duration::ZERO,
+            pulse_len,
+            tempo,
+            bpq,
+            pulse,
+        }
+    }
+
+    /// changes the tempo from the next pulse on.
+    pub fn set_tempo(&mut self, tempo: f64, bpq: f64) {
+        if (tempo == self.tempo && bpq == self.bpq) || tempo <= 0.0 || bpq <= 0.0 {
+            return;
+        }
+
+        self.anchor_time = self.deadline(self.pulse);
+        self.anchor_pulse = self.pulse;
+        self.pulse_len = pulse_len(tempo, bpq);
+        self.tempo = tempo;
+        self.bpq = bpq;
+    }
+
+    /// the time the pulse should happen at. only valid for pulses since the last tempo change.
+    pub fn deadline(&self, pulse: u64) -> Duration {
+        let pulses = pulse.saturating_sub(self.anchor_pulse) as f64;
+
+        self.anchor_time + Duration::from_secs_f64(pulses * self.pulse_len)
+    }
+
+    /// how long until the next pulse should happen. None if it is already due.
+    pub fn time_until_due(&self) -> Option<Duration> {
+        let (now, deadline) = (self.clock.now(), self.deadline(self.pulse));
+
+        (now < deadline).then(|| deadline - now)
+    }
+
+    /// true if the next pulse should have happened.
+    pub fn is_due(&self) -> bool {
+        self.time_until_due().is_none()
+    }
+
+    /// makes the next pulse due now, skipping the ones missed while the scheduler wasn't used.
+    pub fn resync(&mut self) {
+        self.anchor_time = self.clock.now();
+        self.anchor_pulse = self.pulse;
+    }
+
+    /// marks the next pulse as having happened & returns it.
+    pub fn advance(&mut self) -> u64 {
+        let pulse = self.pulse;
+        self.pulse += 1;
+
+        pulse
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, rc::Rc};
+
+    /// a clock that only moves when slept on, and always wakes up late. clones share the time.
+    #[derive(Clone)]
+    struct MockClock {
+        now: Rc<Cell<Duration>>,
+        /// how late every wake up is
+        lateness: Duration,
+    }
+
+    impl MockClock {
+        fn new(lateness: Duration) -> Self {
+            Self {
+                now: Rc::new(Cell::new(Duration::ZERO)),
+                lateness,
+            }
+        }
+
+        /// simulates the time spent doing work between pulses.
+        fn work(&self, amt: Duration) {
+            self.now.set(self.now.get() + amt);
+        }
+
+        fn sleep(&self, amt: Duration) {
+            self.work(amt + self.lateness);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Duration {
+            self.now.get()
+        }
+    }
+
+    /// sleeps until the next pulse, the same way the sequencer does.
+    fn wait(scheduler: &mut PulseScheduler<MockClock>, clock: &MockClock) -> u64 {
+        if let Some(timeout) = scheduler.time_until_due() {
+            clock.sleep(timeout);
+        }
+
+        scheduler.advance()
+    }
+
+    const PULSES: u64 = 10_000;
+
+    #[test]
+    fn no_drift_over_10_000_pulses() {
+        let clock = MockClock::new(Duration::from_micros(700));
+        let mut scheduler = PulseScheduler::new(clock.clone(), 99.0, 24.0);
+
+        for _ in 0..PULSES {
+            clock.work(Duration::from_micros(300));
+            wait(&mut scheduler, &clock);
+        }
+
+        let expected = Duration::from_secs_f64(pulse_len(99.0, 24.0) * (PULSES - 1) as f64);
+        let drift = clock.now().abs_diff(expected);
+
+        // only the last pulse's lateness should show up, not PULSES worth of it.
+        assert!(
+            drift <= Duration::from_millis(1),
+            "drifted by {drift:?} over {PULSES} pulses"
+        );
+    }
+
+    #[test]
+    fn no_drift_across_tempo_changes() {
+        let clock = MockClock::new(Duration::from_micros(700));
+        let mut scheduler = PulseScheduler::new(clock.clone(), 99.0, 24.0);
+
+        for _ in 0..PULSES / 2 {
+            wait(&mut scheduler, &clock);
+        }
+
+        scheduler.set_tempo(140.0, 24.0);
+
+        for _ in 0..PULSES / 2 {
+            wait(&mut scheduler, &clock);
+        }
+
+        let expected = pulse_len(99.0, 24.0) * (PULSES / 2) as f64
+            + pulse_len(140.0, 24.0) * (PULSES / 2 - 1) as f64;
+        let drift = clock.now().abs_diff(Duration::from_secs_f64(expected));
+
+        assert!(
+            drift <= Duration::from_millis(1),
+            "drifted by {drift:?} over {PULSES} pulses"
+        );
+    }
+
+    #[test]
+    fn late_pulses_catch_up() {
+        let clock = MockClock::new(Duration::ZERO);
+        let mut scheduler = PulseScheduler::new(clock.clone(), 120.0, 24.0);
+
+        wait(&mut scheduler, &clock);
+        // stall for 10 pulses worth of time.
+        clock.work(Duration::from_secs_f64(pulse_len(120.0, 24.0) * 10.0));
+
+        let mut caught_up = 0;
+
+        while scheduler.is_due() {
+            scheduler.advance();
+            caught_up += 1;
+        }
+
+        assert_eq!(caught_up, 10);
+    }
+
+    #[test]
+    fn resync_skips_missed_pulses() {
+        let clock = MockClock::new(Duration::ZERO);
+        let mut scheduler = PulseScheduler::new(clock.clone(), 120.0, 24.0);
+
+        wait(&mut scheduler, &clock);
+        clock.work(Duration::from_secs_f64(pulse_len(120.0, 24.0) * 10.0));
+        scheduler.resync();
+
+        assert!(scheduler.is_due());
+        scheduler.advance();
+        assert!(!scheduler.is_due());
+    }
+}
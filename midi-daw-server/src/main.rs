@@ -1,14 +1,18 @@
 use crate::{
+    clock::MonotonicClock,
     midi::{dev::new_midi_dev, out::midi_out},
-    sequencer::sequencer_start,
+    sequencer::{CONTROL_QUEUE_LEN, sequencer_start},
     server::message_bus::MbServer,
 };
-use crossbeam::channel::unbounded;
+use crossbeam::channel::{bounded, unbounded};
+use fx_hash::FxHashSet;
 use std::{
     sync::{Arc, RwLock},
     thread::spawn,
 };
+use tokio::sync::watch;
 
+pub mod clock;
 pub mod midi;
 pub mod sequencer;
 pub mod server;
@@ -20,12 +24,21 @@ pub async fn main() -> std::io::Result<()> {
     // tempo
     let tempo = Arc::new(RwLock::new(99.0));
     let bpq = Arc::new(RwLock::new(24.0));
-    let pulse_counter = Arc::new(RwLock::new(0));
+    let routes = Arc::new(RwLock::new(Vec::new()));
+    let virtual_devs = Arc::new(RwLock::new(FxHashSet::default()));
+    let clock = MonotonicClock::new();
+    // the last sync pulse midi out handled, so the api can wait on the pulse clock.
+    let (pulse_watch_tx, pulse_watch_rx) = watch::channel(0);
 
     // prepare mpsc.
     let (midi_msg_out_tx, midi_msg_out_rx) = unbounded();
+    let (midi_req_tx, midi_req_rx) = unbounded();
+    let (transport_tx, transport_rx) = unbounded();
+    let (pulse_tx, pulse_rx) = unbounded();
+    let (midi_in_tx, midi_in_rx) = unbounded();
     let (new_midi_dev_tx, new_midi_dev_rx) = unbounded();
-    let (sequencer_control_tx, sequencer_control_rx) = unbounded();
+    let (sequencer_control_tx, sequencer_control_rx) = bounded(CONTROL_QUEUE_LEN);
+    let (automation_tx, automation_rx) = unbounded();
     // let (from_sequence_tx, from_sequencer_rx) = unbounded();
     let (mb_server, server_tx) = MbServer::new();
 
@@ -35,25 +48,58 @@ pub async fn main() -> std::io::Result<()> {
             let tempo = tempo.clone();
             let bpq = bpq.clone();
 
-            move || midi_out(midi_msg_out_rx, new_midi_dev_rx, tempo, bpq, pulse_counter)
+            move || {
+                midi_out(
+                    midi_msg_out_rx,
+                    midi_req_rx,
+                    transport_rx,
+                    pulse_rx,
+                    new_midi_dev_rx,
+                    automation_rx,
+                    tempo,
+                    bpq,
+                    pulse_watch_tx,
+                )
+            }
         });
 
         // start a thread for midi device discovery.
-        let new_midi_dev_tx = new_midi_dev_tx.clone();
-        let midi_dev_jh = spawn(move || new_midi_dev(new_midi_dev_tx));
+        let midi_dev_jh = spawn({
+            let new_midi_dev_tx = new_midi_dev_tx.clone();
+            let routes = routes.clone();
+            let midi_msg_out_tx = midi_msg_out_tx.clone();
 
-        // start a automation thread.
-        // let (automation_tx, automation_rx) = unbounded();
-        //
-        // let automation_jh = spawn(move || automation(automation_rx, midi_msg_out_tx.clone()));
+            move || new_midi_dev(new_midi_dev_tx, midi_in_tx, routes, midi_msg_out_tx, clock)
+        });
 
         // start sequencer
         let sequencer_jh = spawn({
             let server_tx = server_tx.clone();
             let tempo = tempo.clone();
             let bpq = bpq.clone();
+            let automation_tx = automation_tx.clone();
+            let midi_req_tx = midi_req_tx.clone();
+            let routes = routes.clone();
+            let new_midi_dev_tx = new_midi_dev_tx.clone();
+            let virtual_devs = virtual_devs.clone();
 
-            move || sequencer_start(tempo, bpq, sequencer_control_rx, server_tx)
+            move || {
+                sequencer_start(
+                    tempo,
+                    bpq,
+                    sequencer_control_rx,
+                    server_tx,
+                    automation_tx,
+                    midi_req_tx,
+                    transport_tx,
+                    pulse_tx,
+                    midi_in_rx,
+                    routes,
+                    new_midi_dev_tx,
+                    virtual_devs,
+                    clock,
+                )
+            }
         });
 
         (midi_out_jh, midi_dev_jh, sequencer_jh)
@@ -64,8 +110,13 @@ pub async fn main() -> std::io::Result<()> {
         tempo,
         bpq,
         midi_msg_out_tx,
+        midi_req_tx,
+        pulse_watch_rx,
         new_midi_dev_tx,
         sequencer_control_tx,
+        automation_tx,
+        routes,
+        virtual_devs,
         mb_server,
         server_tx,
     )
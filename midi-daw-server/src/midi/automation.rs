@@ -1,9 +1,193 @@
-// use crossbeam::channel::{Receiver, Sender};
-// use midi_msg::MidiMsg;
-//
-// pub fn automation(
-//     new_dev_tx: Receiver<AutomationConf>,
-//     midi_msg_out: Sender<(String, MidiMsg)>,
-// ) -> ! {
-//  loop {}
-// }
+use actix::dev::OneshotSender;
+use fx_hash::{FxHashMap, FxHashSet};
+use midi_daw_types::{
+    MidiChannel, MidiMsg, SequencerError, StartAutomationBody,
+    automation::{AutomationTrait, AutomationTypes},
+};
+use midi_msg::{ChannelVoiceMsg, ControlChange};
+use tracing::*;
+
+/// makes the automation described by conf. this reads wave-table files, so it blocks.
+pub fn make_automation(conf: &StartAutomationBody) -> Result<AutomationTypes, SequencerError> {
+    AutomationTypes::try_from(conf.conf.clone()).map_err(|message| {
+        error!(
+            "making automation, \"{}\", failed with error: {message}",
+            conf.name
+        );
+
+        SequencerError::InvalidAutomation {
+            name: conf.name.clone(),
+            message,
+        }
+    })
+}
+
+pub enum AutomationCmd {
+    /// starts an automation, replacing any running automation with the same name. the automation
+    /// is made from its config before it's sent, (see make_automation), so wave-table files
+    /// aren't read on the midi out thread.
+    Start {
+        conf: StartAutomationBody,
+        automation: AutomationTypes,
+    },
+    /// stops the named automation
+    Stop(String),
+    StopAll,
+    /// re-phases the tempo synced automations, sent when the sequencer resets the sync pulse
+    SyncReset,
+    /// lists the running automations
+    List {
+        responder: OneshotSender<Vec<StartAutomationBody>>,
+    },
+}
+
+struct RunningAutomation {
+    conf: StartAutomationBody,
+    automation: AutomationTypes,
+    /// the last message sent, used to avoid re-sending unchanged values
+    last_msg: Option<MidiMsg>,
+    /// notes being held on the automation's device & channel, used to gate envelopes
+    held_notes: FxHashSet<u8>,
+}
+
+/// holds the automations that run server side. stepped once per sync pulse by the midi_out thread.
+#[derive(Default)]
+pub struct AutomationEngine {
+    automations: FxHashMap<String, RunningAutomation>,
+    update_rate: f64,
+    tempo: f64,
+}
+
+impl AutomationEngine {
+    pub fn handle_cmd(&mut self, cmd: AutomationCmd) {
+        match cmd {
+            AutomationCmd::Start { conf, automation } => self.start(conf, automation),
+            AutomationCmd::Stop(name) => {
+                if self.automations.remove(&name).is_none() {
+                    warn!("no automation named, \"{name}\", is running");
+                }
+            }
+            AutomationCmd::StopAll => self.automations.clear(),
+            AutomationCmd::SyncReset => self
+                .automations
+                .values_mut()
+                .for_each(|running| running.automation.reset()),
+            AutomationCmd::List { responder } => {
+                let confs = self
+                    .automations
+                    .values()
+                    .map(|running| running.conf.clone())
+                    .collect();
+
+                if let Err(e) = responder.send(confs) {
+                    error!("sending automation list failed with error: {e:?}");
+                }
+            }
+        }
+    }
+
+    /// starts automation, made from conf.
+    fn start(&mut self, conf: StartAutomationBody, mut automation: AutomationTypes) {
+        if self.update_rate > 0.0 {
+            automation.set_update_rate(self.update_rate);
+        }
+
+        if self.tempo > 0.0 {
+            automation.set_tempo(self.tempo);
+        }
+
+        info!("starting automation, \"{}\"", conf.name);
+
+        self.automations.insert(
+            conf.name.clone(),
+            RunningAutomation {
+                conf,
+                automation,
+                last_msg: None,
+                held_notes: FxHashSet::default(),
+            },
+        );
+    }
+
+    /// gates the automations that target the device & channel a note was sent to. each note-on
+    /// (re)starts the automation, it is released once every note is off.
+    pub fn note_event(&mut self, dev_name: &str, msg: &midi_msg::MidiMsg) {
+        let midi_msg::MidiMsg::ChannelVoice { channel, msg } = msg else {
+            return;
+        };
+        let (note, is_on) = match *msg {
+            ChannelVoiceMsg::NoteOn { note, velocity } => (note, velocity > 0),
+            ChannelVoiceMsg::NoteOff { note, velocity: _ } => (note, false),
+            _ => return,
+        };
+
+        self.automations
+            .values_mut()
+            .filter(|running| {
+                running.conf.midi_dev == dev_name
+                    && midi_msg::Channel::from(running.conf.channel) == *channel
+            })
+            .for_each(|running| {
+                if is_on {
+                    running.held_notes.insert(note);
+                    running.automation.gate_on();
+                } else if running.held_notes.remove(&note) && running.held_notes.is_empty() {
+                    running.automation.gate_off();
+                }
+            });
+    }
+
+    /// steps every running automation by one sync pulse. returns the midi messages to send.
+    ///
+    /// update_rate is the number of sync pulses per second.
+    pub fn pulse(&mut self, tempo: f64, update_rate: f64) -> Vec<(String, midi_msg::MidiMsg)> {
+        if tempo != self.tempo {
+            self.tempo = tempo;
+            self.automations
+                .values_mut()
+                .for_each(|running| running.automation.set_tempo(tempo));
+        }
+
+        if update_rate != self.update_rate {
+            self.update_rate = update_rate;
+            self.automations
+                .values_mut()
+                .for_each(|running| running.automation.set_update_rate(update_rate));
+        }
+
+        self.automations
+            .values_mut()
+            .filter_map(|running| {
+                let value = running.automation.step();
+                let msg = running
+                    .conf
+                    .target
+                    .scale(value, running.automation.is_bipolar());
+
+                if running.last_msg.as_ref() == Some(&msg) {
+                    return None;
+                }
+
+                running.last_msg = Some(msg.clone());
+
+                to_midi_msg(running.conf.channel, &msg)
+                    .map(|msg| (running.conf.midi_dev.clone(), msg))
+            })
+            .collect()
+    }
+}
+
+fn to_midi_msg(channel: MidiChannel, msg: &MidiMsg) -> Option<midi_msg::MidiMsg> {
+    let msg = match *msg {
+        MidiMsg::CC { control, value } => midi_msg::ChannelVoiceMsg::ControlChange {
+            control: ControlChange::CC { control, value },
+        },
+        MidiMsg::PitchBend { bend } => midi_msg::ChannelVoiceMsg::PitchBend { bend },
+        _ => return None,
+    };
+
+    Some(midi_msg::MidiMsg::ChannelVoice {
+        channel: channel.into(),
+        msg,
+    })
+}
@@ -0,0 +1,204 @@
+use midi_msg::{MidiMsg, SystemCommonMsg, SystemRealTimeMsg};
+use std::time::Duration;
+
+/// the number of midi clock messages per quarter note, fixed by the midi spec.
+const MIDI_CLOCK_PPQ: f64 = 24.0;
+/// how much each midi clock message moves the tempo estimate.
+const TEMPO_SMOOTHING: f64 = 0.1;
+/// clock messages further apart than this are treated as the clock starting over, or as the clock
+/// having gone away.
+pub const MAX_TICK_GAP: Duration = Duration::from_secs(1);
+
+/// transport changes & clock messages received from an external midi clock.
+#[derive(Clone, Copy, Debug)]
+pub enum ClockInEvent {
+    /// a midi clock message
+    Tick,
+    Start,
+    Stop,
+    Continue,
+    /// the position to play from, in sixteenth notes
+    SongPosition(u16),
+}
+
+/// follows an external midi clock, converting its 24 pulses per quarter note into sync pulses.
+#[derive(Default)]
+pub struct ClockIn {
+    /// sync pulses received but not used yet
+    owed: f64,
+    running: bool,
+    last_tick: Option<Duration>,
+    tempo: Option<f64>,
+}
+
+impl ClockIn {
+    /// handles a message from the clock source. now is the time it was received. returns the
+    /// transport change or clock message, if any.
+    pub fn handle(&mut self, msg: &MidiMsg, bpq: f64, now: Duration) -> Option<ClockInEvent> {
+        match msg {
+            MidiMsg::SystemRealTime {
+                msg: SystemRealTimeMsg::TimingClock,
+            } => {
+                self.tick(now);
+
+                if self.running {
+                    self.owed += bpq / MIDI_CLOCK_PPQ;
+                }
+
+                Some(ClockInEvent::Tick)
+            }
+            MidiMsg::SystemRealTime {
+                msg: SystemRealTimeMsg::Start,
+            } => {
+                self.running = true;
+                self.owed = 0.0;
+
+                Some(ClockInEvent::Start)
+            }
+            MidiMsg::SystemRealTime {
+                msg: SystemRealTimeMsg::Continue,
+            } => {
+                self.running = true;
+
+                Some(ClockInEvent::Continue)
+            }
+            MidiMsg::SystemRealTime {
+                msg: SystemRealTimeMsg::Stop,
+            } => {
+                self.running = false;
+                self.owed = 0.0;
+
+                Some(ClockInEvent::Stop)
+            }
+            MidiMsg::SystemCommon {
+                msg: SystemCommonMsg::SongPosition(position),
+            } => Some(ClockInEvent::SongPosition(*position)),
+            _ => None,
+        }
+    }
+
+    /// true while the external clock is running & its clock messages are still arriving.
+    pub fn is_following(&self, now: Duration) -> bool {
+        self.running
+            && self
+                .last_tick
+                .is_some_and(|last_tick| now.saturating_sub(last_tick) <= MAX_TICK_GAP)
+    }
+
+    /// true if there is a received sync pulse to use.
+    pub fn has_pulse(&self) -> bool {
+        self.owed >= 1.0
+    }
+
+    /// uses up one received sync pulse. returns false if there are none.
+    pub fn take_pulse(&mut self) -> bool {
+        if self.has_pulse() {
+            self.owed -= 1.0;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// the tempo of the external clock, rounded to a tenth of a BPM.
+    pub fn tempo(&self) -> Option<f64> {
+        self.tempo.map(|tempo| (tempo * 10.0).round() / 10.0)
+    }
+
+    fn tick(&mut self, now: Duration) {
+        let last_tick = self.last_tick.replace(now);
+
+        let Some(gap) = last_tick.map(|last_tick| now.saturating_sub(last_tick)) else {
+            return;
+        };
+
+        if gap.is_zero() || gap > MAX_TICK_GAP {
+            return;
+        }
+
+        let tempo = 60.0 / (gap.as_secs_f64() * MIDI_CLOCK_PPQ);
+
+        self.tempo = Some(match self.tempo {
+            Some(old) => old + (tempo - old) * TEMPO_SMOOTHING,
+            None => tempo,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn real_time(msg: SystemRealTimeMsg) -> MidiMsg {
+        MidiMsg::SystemRealTime { msg }
+    }
+
+    #[test]
+    fn ticks_only_pulse_while_running() {
+        let mut clock_in = ClockIn::default();
+        let tick = real_time(SystemRealTimeMsg::TimingClock);
+
+        // ticks are still passed on while the clock is stopped, but don't move the sequencer.
+        let event = clock_in.handle(&tick, 24.0, Duration::ZERO);
+        assert!(matches!(event, Some(ClockInEvent::Tick)));
+        assert!(!clock_in.has_pulse());
+
+        let event = clock_in.handle(&real_time(SystemRealTimeMsg::Start), 24.0, Duration::ZERO);
+        assert!(matches!(event, Some(ClockInEvent::Start)));
+        clock_in.handle(&tick, 24.0, Duration::ZERO);
+        assert!(clock_in.take_pulse());
+
+        let event = clock_in.handle(&real_time(SystemRealTimeMsg::Stop), 24.0, Duration::ZERO);
+        assert!(matches!(event, Some(ClockInEvent::Stop)));
+        clock_in.handle(&tick, 24.0, Duration::ZERO);
+        assert!(!clock_in.take_pulse());
+
+        let event = clock_in.handle(
+            &real_time(SystemRealTimeMsg::Continue),
+            24.0,
+            Duration::ZERO,
+        );
+        assert!(matches!(event, Some(ClockInEvent::Continue)));
+        clock_in.handle(&tick, 24.0, Duration::ZERO);
+        assert!(clock_in.take_pulse());
+    }
+
+    #[test]
+    fn only_follows_a_running_clock() {
+        let mut clock_in = ClockIn::default();
+        let tick = real_time(SystemRealTimeMsg::TimingClock);
+        let gap = Duration::from_millis(20);
+
+        // nothing connected yet
+        assert!(!clock_in.is_following(Duration::ZERO));
+
+        clock_in.handle(&real_time(SystemRealTimeMsg::Start), 24.0, Duration::ZERO);
+        clock_in.handle(&tick, 24.0, gap);
+        assert!(clock_in.is_following(gap * 2));
+
+        // the clock went away without stopping
+        assert!(!clock_in.is_following(gap + MAX_TICK_GAP * 2));
+
+        clock_in.handle(&tick, 24.0, gap * 3);
+        assert!(clock_in.is_following(gap * 3));
+
+        // stopped, but still sending clock messages
+        clock_in.handle(&real_time(SystemRealTimeMsg::Stop), 24.0, gap * 4);
+        clock_in.handle(&tick, 24.0, gap * 5);
+        assert!(!clock_in.is_following(gap * 5));
+    }
+
+    #[test]
+    fn tempo_comes_from_when_ticks_arrived() {
+        let mut clock_in = ClockIn::default();
+        let tick = real_time(SystemRealTimeMsg::TimingClock);
+
+        // ticks that arrived 25ms apart are 100 BPM.
+        for i in 0..48 {
+            clock_in.handle(&tick, 24.0, Duration::from_millis(25 * i));
+        }
+
+        assert_eq!(clock_in.tempo(), Some(100.0));
+    }
+}
@@ -0,0 +1,84 @@
+use fx_hash::FxHashSet;
+use midi_msg::{MidiMsg, SystemCommonMsg, SystemRealTimeMsg};
+
+/// the number of midi clock messages per quarter note, fixed by the midi spec.
+const MIDI_CLOCK_PPQ: f64 = 24.0;
+
+/// transport changes sent from the sequencer to the devices following the midi clock.
+#[derive(Clone, Copy, Debug)]
+pub enum Transport {
+    /// one midi clock message, passed on from the external clock the sequencer follows
+    Clock,
+    /// playback started from the top
+    Start,
+    /// playback stopped or paused
+    Stop,
+    /// playback continued from position, (in sixteenth notes)
+    Continue { position: u16 },
+}
+
+impl Transport {
+    pub fn to_midi_msgs(self) -> Vec<MidiMsg> {
+        match self {
+            Self::Clock => vec![MidiMsg::SystemRealTime {
+                msg: SystemRealTimeMsg::TimingClock,
+            }],
+            Self::Start => vec![MidiMsg::SystemRealTime {
+                msg: SystemRealTimeMsg::Start,
+            }],
+            Self::Stop => vec![MidiMsg::SystemRealTime {
+                msg: SystemRealTimeMsg::Stop,
+            }],
+            Self::Continue { position } => vec![
+                MidiMsg::SystemCommon {
+                    msg: SystemCommonMsg::SongPosition(position),
+                },
+                MidiMsg::SystemRealTime {
+                    msg: SystemRealTimeMsg::Continue,
+                },
+            ],
+        }
+    }
+}
+
+/// tracks which devices get midi clock & converts sync pulses into midi clock messages.
+#[derive(Default)]
+pub struct ClockOut {
+    devs: FxHashSet<String>,
+    /// midi clock messages owed but not sent yet, used when BPQ isn't 24
+    owed: f64,
+}
+
+impl ClockOut {
+    pub fn set(&mut self, dev_name: String, on: bool) {
+        if on {
+            self.devs.insert(dev_name);
+        } else {
+            self.devs.remove(&dev_name);
+        }
+    }
+
+    pub fn devs(&self) -> impl Iterator<Item = &String> {
+        self.devs.iter()
+    }
+
+    /// the transport restarted, so line the midi clock up with it.
+    pub fn on_transport(&mut self, transport: Transport) {
+        if matches!(transport, Transport::Start | Transport::Continue { .. }) {
+            self.owed = 0.0;
+        }
+    }
+
+    /// returns the number of midi clock messages to send this sync pulse.
+    pub fn pulse(&mut self, bpq: f64) -> usize {
+        if self.devs.is_empty() || bpq <= 0.0 {
+            return 0;
+        }
+
+        self.owed += MIDI_CLOCK_PPQ / bpq;
+        let n = self.owed.floor();
+        self.owed -= n;
+
+        n as usize
+    }
+}
@@ -1,8 +1,17 @@
-use crate::midi::MidiDev;
+use crate::{
+    clock::{Clock, MonotonicClock},
+    midi::{
+        MidiDev,
+        thru::{Routes, route_midi},
+    },
+    server::MidiOut,
+};
 use crossbeam::channel::Sender;
 use fx_hash::{FxHashMap, FxHashSet};
-use midir::MidiOutput;
+use midi_msg::MidiMsg;
+use midir::{Ignore, MidiInput, MidiInputConnection, MidiOutput};
 use std::{thread::sleep, time::Duration};
+use tracing::log::*;
 
 pub fn fmt_dev_name(name: impl ToString) -> String {
     let name = name.to_string();
@@ -10,8 +19,20 @@ pub fn fmt_dev_name(name: impl ToString) -> String {
     format!("{}:{}", tokens[0], tokens.last().unwrap())
 }
 
-pub fn new_midi_dev(new_dev_tx: Sender<MidiDev>) -> ! {
+/// true for the ports this server made, so it doesn't listen to its own output.
+pub fn is_own_port(dev_name: &str) -> bool {
+    dev_name.starts_with("MIDI-DAW")
+}
+
+pub fn new_midi_dev(
+    new_dev_tx: Sender<MidiDev>,
+    midi_in_tx: Sender<(String, MidiMsg, Duration)>,
+    routes: Routes,
+    midi_out_tx: MidiOut,
+    clock: MonotonicClock,
+) -> ! {
     let mut midi_devs: FxHashMap<String, String> = FxHashMap::default();
+    let mut midi_ins: FxHashMap<String, MidiInputConnection<()>> = FxHashMap::default();
 
     loop {
         // check for new devices
@@ -45,6 +66,87 @@ pub fn new_midi_dev(new_dev_tx: Sender<MidiDev>) -> ! {
 
         midi_devs = midi_devs_names.into_iter().collect();
 
+        // connect to new midi inputs
+        let midi_in = MidiInput::new("MIDI-DAW").unwrap();
+        let midi_in_names: FxHashSet<(String, String)> = midi_in
+            .ports()
+            .into_iter()
+            .filter_map(|port| {
+                midi_in
+                    .port_name(&port)
+                    .map_or(None, |name| Some((fmt_dev_name(&name), port.id())))
+            })
+            .filter(|(dev_name, _)| !is_own_port(dev_name))
+            .collect();
+
+        for (dev_name, dev_id) in midi_in_names.iter() {
+            if !midi_ins.contains_key(dev_name)
+                && let Some(conn) = connect_midi_in(
+                    dev_name,
+                    dev_id,
+                    midi_in_tx.clone(),
+                    routes.clone(),
+                    midi_out_tx.clone(),
+                    clock,
+                )
+            {
+                midi_ins.insert(dev_name.clone(), conn);
+            }
+        }
+
+        // drop the inputs that went away
+        midi_ins.retain(|dev_name, _| midi_in_names.iter().any(|(name, _)| name == dev_name));
+
         sleep(Duration::from_millis(100));
     }
 }
+
+/// listens to a midi input, forwarding every message it sends to midi_in_tx, (with the time it
+/// arrived), & the outputs routed from it.
+fn connect_midi_in(
+    dev_name: &str,
+    dev_id: &str,
+    midi_in_tx: Sender<(String, MidiMsg, Duration)>,
+    routes: Routes,
+    midi_out_tx: MidiOut,
+    clock: MonotonicClock,
+) -> Option<MidiInputConnection<()>> {
+    let Ok(mut midi_in) = MidiInput::new(&format!("MIDI-DAW-IN-{dev_name}")) else {
+        error!("failed to make midi input for \"{dev_name}\"");
+        return None;
+    };
+    // midi clock is a timing message, so nothing can be ignored.
+    midi_in.ignore(Ignore::None);
+
+    let Some(port) = midi_in.find_port_by_id(dev_id.to_string()) else {
+        warn!("unknown device id \"{dev_id}\"");
+        return None;
+    };
+
+    let name = dev_name.to_string();
+
+    match midi_in.connect(
+        &port,
+        dev_name,
+        move |_stamp, bytes, _| match MidiMsg::from_midi(bytes) {
+            Ok((msg, _len)) => {
+                let now = clock.now();
+
+                // thru first, so routing isn't held up by the sequencer.
+                route_midi(&routes, &midi_out_tx, &name, &msg);
+                _ = midi_in_tx.send((name.clone(), msg, now));
+            }
+            Err(e) => debug!("failed to parse midi input from \"{name}\": {e}"),
+        },
+        (),
+    ) {
+        Ok(conn) => {
+            info!("listening to midi input \"{dev_name}\"");
+            Some(conn)
+        }
+        Err(e) => {
+            warn!("connecting to midi input \"{dev_name}\" failed with error: {e}");
+            None
+        }
+    }
+}
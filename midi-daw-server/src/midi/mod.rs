@@ -1,9 +1,31 @@
+use actix::dev::OneshotSender;
+use fx_hash::FxHashSet;
+use std::sync::{Arc, RwLock};
+
 pub mod automation;
+pub mod clock_in;
+pub mod clock_out;
 pub mod dev;
 pub mod out;
+pub mod thru;
+
+/// the virtual midi devices made through the api, shared between the api & the sequencer.
+pub type VirtualDevs = Arc<RwLock<FxHashSet<String>>>;
 
 pub enum MidiDev {
-    Added { dev_name: String, dev_id: String },
+    Added {
+        dev_name: String,
+        dev_id: String,
+    },
     RMed(String),
     CreateVirtual(String),
+    /// turns midi clock, start/stop & song position output on or off for a device
+    SetClockOut {
+        dev_name: String,
+        on: bool,
+    },
+    /// lists the devices with midi clock out turned on
+    ListClockOut {
+        responder: OneshotSender<Vec<String>>,
+    },
 }
@@ -1,16 +1,19 @@
 use crate::{
-    midi::MidiDev,
+    clock::note_pulses,
+    midi::{
+        MidiDev,
+        automation::{AutomationCmd, AutomationEngine},
+        clock_out::{ClockOut, Transport},
+    },
     server::{BPQ, Tempo},
 };
-use crossbeam::channel::Receiver;
+use crossbeam::channel::{Receiver, Select};
 use fx_hash::FxHashMap;
-use midi_msg::MidiMsg;
-use midir::{MidiOutput, os::unix::VirtualOutput};
-use std::{
-    sync::{Arc, RwLock},
-    thread::{sleep, spawn},
-    time::Duration,
-};
+use midi_daw_types::{MidiChannel, MidiReqBody};
+use midi_msg::{ChannelVoiceMsg, ControlChange, MidiMsg};
+use midir::{MidiOutput, MidiOutputConnection, os::unix::VirtualOutput};
+use std::sync::{Arc, RwLock};
+use tokio::sync::watch;
 use tracing::log::*;
 
 pub fn unwrap_rw_lock<T>(thing: &Arc<RwLock<T>>, default: T) -> T
@@ -24,105 +27,300 @@ where
     }
 }
 
+/// a sync pulse from the sequencer.
+#[derive(Clone, Copy, Debug)]
+pub struct Pulse {
+    /// counted from when the server started
+    pub n: u64,
+    /// true while following an external midi clock. its clock messages are passed on as they
+    /// arrive, rather then made from the pulses.
+    pub external: bool,
+}
+
+/// a note-off waiting for its pulse.
+struct NoteOff {
+    pulse: u64,
+    dev_name: String,
+    channel: MidiChannel,
+    note: u8,
+    velocity: u8,
+}
+
+impl NoteOff {
+    fn is_for(&self, dev_name: &str, channel: MidiChannel, note: u8) -> bool {
+        self.dev_name == dev_name && self.channel == channel && self.note == note
+    }
+
+    fn to_midi_msg(&self) -> MidiMsg {
+        MidiMsg::ChannelVoice {
+            channel: self.channel.into(),
+            msg: ChannelVoiceMsg::NoteOff {
+                note: self.note,
+                velocity: self.velocity,
+            },
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn midi_out(
     midi_msg_out: Receiver<(String, MidiMsg /*, Sender<()>*/)>,
+    midi_reqs: Receiver<MidiReqBody>,
+    transport: Receiver<Transport>,
+    pulses: Receiver<Pulse>,
     new_dev: Receiver<MidiDev>,
+    automation_cmds: Receiver<AutomationCmd>,
     tempo: Tempo,
     bpq: BPQ,
-    pulse_counter: Arc<RwLock<usize>>,
+    pulse_watch: watch::Sender<u64>,
 ) -> ! {
     let mut midi_devs = FxHashMap::default();
+    let mut automations = AutomationEngine::default();
+    let mut note_offs: Vec<NoteOff> = Vec::default();
+    let mut clock_out = ClockOut::default();
+    let mut pulse = 0;
 
     loop {
-        // start a sleep thread to sleep for sleep_time.
-        let sleep_thread = spawn({
-            // calculate sleep_time based on BPQ & tempo.
-            let (tempo, beats) = (unwrap_rw_lock(&tempo, 99.), unwrap_rw_lock(&bpq, 24.));
-            let sleep_time = Duration::from_secs_f64((60.0 / tempo) / beats);
+        // sleep until there is something to do. the sequencer sends the sync pulses, so midi out
+        // keeps time with it, (& with the external clock when it follows one).
+        let mut sel = Select::new();
+        sel.recv(&midi_msg_out);
+        sel.recv(&midi_reqs);
+        sel.recv(&transport);
+        sel.recv(&pulses);
+        sel.recv(&new_dev);
+        sel.recv(&automation_cmds);
+        sel.ready();
 
-            move || {
-                sleep(sleep_time);
-            }
-        });
+        // the sequencer sends transport changes before the notes that follow them.
+        while let Ok(transport) = transport.try_recv() {
+            clock_out.on_transport(transport);
+            send_clock(&mut midi_devs, &clock_out, &transport.to_midi_msgs());
+        }
 
         // poll for new midi devices
         while let Ok(dev_msg) = new_dev.try_recv() {
-            match dev_msg {
-                MidiDev::Added { dev_name, dev_id } => {
-                    let midi_out = MidiOutput::new(&format!("MIDI-DAW-{dev_name}")).unwrap();
-
-                    if let Some(dev) = midi_out.find_port_by_id(dev_id.to_string()).clone() {
-                        if let Ok(dev) = midi_out.connect(&dev, &dev_name) {
-                            midi_devs.insert(dev_name, dev);
-                        } else {
-                            warn!("device named \"{dev_name}\" is no longer connected")
-                        }
-                    } else {
-                        warn!("unknown device id \"{dev_id}\"")
-                    }
-                }
-                MidiDev::RMed(dev_name) => {
-                    midi_devs.remove(&dev_name);
+            handle_dev_msg(&mut midi_devs, &mut clock_out, dev_msg);
+        }
+
+        // poll for automation controls
+        while let Ok(cmd) = automation_cmds.try_recv() {
+            automations.handle_cmd(cmd);
+        }
+
+        // pulses come before the midi sent on them, so note-offs are scheduled from the right one.
+        while let Ok(next_pulse) = pulses.try_recv() {
+            pulse = next_pulse.n;
+            let (tempo, beats) = (unwrap_rw_lock(&tempo, 99.), unwrap_rw_lock(&bpq, 24.));
+
+            // send midi clock
+            if !next_pulse.external {
+                let timing_clock = Transport::Clock.to_midi_msgs();
+
+                (0..clock_out.pulse(beats))
+                    .for_each(|_| send_clock(&mut midi_devs, &clock_out, &timing_clock));
+            }
+
+            // send the note-offs that are due
+            note_offs.retain(|note_off| {
+                if note_off.pulse > pulse {
+                    return true;
                 }
-                MidiDev::CreateVirtual(dev_name) => {
-                    let midi_out = MidiOutput::new("MIDI-DAW-NEW-DEV").unwrap();
-
-                    if let Ok(dev) = midi_out.create_virtual(&dev_name)
-                        && !midi_devs.contains_key(&dev_name)
-                    {
-                        midi_devs.insert(dev_name, dev);
-                    } else if midi_devs.contains_key(&dev_name) {
-                        info!("device already exists")
-                    } else {
-                        error!("failed to make virtual output device");
-                        // eprintln!("failed to make virtual output device");
+
+                send_midi(
+                    &mut midi_devs,
+                    &mut automations,
+                    note_off.dev_name.clone(),
+                    note_off.to_midi_msg(),
+                );
+
+                false
+            });
+
+            // step automations
+            for (dev_name, msg) in automations.pulse(tempo, (tempo / 60.0) * beats) {
+                if let Some(dev) = midi_devs.get_mut(&dev_name) {
+                    if let Err(e) = dev.send(&msg.to_midi()) {
+                        error!("automation output failed with error {e}");
                     }
+                } else {
+                    error!("the automated midi device, \"{dev_name}\", is not connected.");
                 }
             }
+
+            // let the api know the pulse happened.
+            pulse_watch.send_replace(pulse);
         }
 
-        // poll for msg to send
-        while let Ok(midi_msg) = midi_msg_out.try_recv() {
-            match midi_msg {
-                (dev_name, msg /*, responce_dev*/) if midi_devs.contains_key(&dev_name) => {
-                    // send messages
-                    let Some(dev) = midi_devs.get_mut(&dev_name) else {
-                        error!(
-                            "an error occured finding the midi device with the name \"{dev_name}\""
-                        );
-                        // eprintln!("an error occured finding the midi device with the name \"{dev_name}\"");
-                        continue;
-                    };
+        while let Ok((dev_name, msg)) = midi_msg_out.try_recv() {
+            send_midi(&mut midi_devs, &mut automations, dev_name, msg);
+        }
 
-                    if let Err(e) = dev.send(&msg.to_midi()) {
-                        error!("midi output failed with error {e}");
-                        // eprintln!("midi output failed with error {e}");
-                    }
+        while let Ok(req) = midi_reqs.try_recv() {
+            play_req(
+                &mut midi_devs,
+                &mut automations,
+                &mut note_offs,
+                req,
+                pulse,
+                unwrap_rw_lock(&bpq, 24.),
+            );
+        }
+    }
+}
 
-                    // if let Err(e) = responce_dev.send()
-                }
-                (dev_name, _msg /*, _responce_dev*/) => {
-                    error!("the requested midi device, \"{dev_name}\", is not connected.");
-                    // eprintln!("the requested midi device, \"{dev_name}\", is not connected.");
-                    error!("known devs = {:?}", midi_devs.keys());
+fn handle_dev_msg(
+    midi_devs: &mut FxHashMap<String, MidiOutputConnection>,
+    clock_out: &mut ClockOut,
+    dev_msg: MidiDev,
+) {
+    match dev_msg {
+        MidiDev::Added { dev_name, dev_id } => {
+            let midi_out = MidiOutput::new(&format!("MIDI-DAW-{dev_name}")).unwrap();
+
+            if let Some(dev) = midi_out.find_port_by_id(dev_id.to_string()).clone() {
+                if let Ok(dev) = midi_out.connect(&dev, &dev_name) {
+                    midi_devs.insert(dev_name, dev);
+                } else {
+                    warn!("device named \"{dev_name}\" is no longer connected")
                 }
+            } else {
+                warn!("unknown device id \"{dev_id}\"")
             }
         }
+        MidiDev::RMed(dev_name) => {
+            midi_devs.remove(&dev_name);
+        }
+        MidiDev::CreateVirtual(dev_name) => {
+            let midi_out = MidiOutput::new("MIDI-DAW-NEW-DEV").unwrap();
 
-        // increment pulse_counter.
-        if let Ok(mut counter) = pulse_counter.write() {
-            if counter.to_owned() == usize::MAX {
-                *counter = 0;
+            if let Ok(dev) = midi_out.create_virtual(&dev_name)
+                && !midi_devs.contains_key(&dev_name)
+            {
+                midi_devs.insert(dev_name, dev);
+            } else if midi_devs.contains_key(&dev_name) {
+                info!("device already exists")
             } else {
-                *counter += 1;
+                error!("failed to make virtual output device");
+                // eprintln!("failed to make virtual output device");
             }
         }
+        MidiDev::SetClockOut { dev_name, on } => {
+            info!("midi clock out for \"{dev_name}\" is now on: {on}");
+            clock_out.set(dev_name, on);
+        }
+        MidiDev::ListClockOut { responder } => {
+            if let Err(e) = responder.send(clock_out.devs().cloned().collect()) {
+                error!("sending midi clock devices failed with error: {e:?}");
+            }
+        }
+    }
+}
 
-        // time sync
-        if let Err(e) = sleep_thread.join() {
-            error!(
-                "joinning sleep thread in midi_out thread resultd in error; {e:?}. this likely means that the processing for midi output took longer then the step duration"
-            );
+/// sends msgs to every connected device that has midi clock out turned on.
+fn send_clock(
+    midi_devs: &mut FxHashMap<String, MidiOutputConnection>,
+    clock_out: &ClockOut,
+    msgs: &[MidiMsg],
+) {
+    for dev_name in clock_out.devs() {
+        let Some(dev) = midi_devs.get_mut(dev_name) else {
+            continue;
+        };
+
+        for msg in msgs {
+            if let Err(e) = dev.send(&msg.to_midi()) {
+                error!("midi clock output failed with error {e}");
+            }
         }
     }
 }
+
+fn send_midi(
+    midi_devs: &mut FxHashMap<String, MidiOutputConnection>,
+    automations: &mut AutomationEngine,
+    dev_name: String,
+    msg: MidiMsg,
+) {
+    // send messages
+    let Some(dev) = midi_devs.get_mut(&dev_name) else {
+        error!("the requested midi device, \"{dev_name}\", is not connected.");
+        error!("known devs = {:?}", midi_devs.keys());
+        return;
+    };
+
+    if let Err(e) = dev.send(&msg.to_midi()) {
+        error!("midi output failed with error {e}");
+    }
+
+    automations.note_event(&dev_name, &msg);
+}
+
+/// sends a midi request from the sequencer. note-offs are scheduled on the pulse clock.
+fn play_req(
+    midi_devs: &mut FxHashMap<String, MidiOutputConnection>,
+    automations: &mut AutomationEngine,
+    note_offs: &mut Vec<NoteOff>,
+    req: MidiReqBody,
+    pulse: u64,
+    bpq: f64,
+) {
+    let MidiReqBody {
+        midi_dev: dev_name,
+        channel,
+        msg,
+    } = req;
+
+    let msg = match msg {
+        midi_daw_types::MidiMsg::PlayNote {
+            note,
+            velocity,
+            duration,
+        } => {
+            // a retriggered note ends the one still sounding.
+            if let Some(i) = note_offs
+                .iter()
+                .position(|note_off| note_off.is_for(&dev_name, channel, note))
+            {
+                let note_off = note_offs.swap_remove(i);
+                send_midi(
+                    midi_devs,
+                    automations,
+                    dev_name.clone(),
+                    note_off.to_midi_msg(),
+                );
+            }
+
+            let len = note_pulses(duration, bpq);
+
+            note_offs.push(NoteOff {
+                pulse: pulse + len,
+                dev_name: dev_name.clone(),
+                channel,
+                note,
+                velocity,
+            });
+
+            ChannelVoiceMsg::NoteOn { note, velocity }
+        }
+        midi_daw_types::MidiMsg::StopNote { note } => {
+            note_offs.retain(|note_off| !note_off.is_for(&dev_name, channel, note));
+
+            ChannelVoiceMsg::NoteOff {
+                note,
+                velocity: 127,
+            }
+        }
+        midi_daw_types::MidiMsg::CC { control, value } => ChannelVoiceMsg::ControlChange {
+            control: ControlChange::CC { control, value },
+        },
+        midi_daw_types::MidiMsg::PitchBend { bend } => ChannelVoiceMsg::PitchBend { bend },
+    };
+
+    let msg = MidiMsg::ChannelVoice {
+        channel: channel.into(),
+        msg,
+    };
+
+    send_midi(midi_devs, automations, dev_name, msg);
+}
@@ -0,0 +1,189 @@
+use crate::server::MidiOut;
+use midi_daw_types::{MidiMsgKind, Route};
+use midi_msg::{Channel, ChannelVoiceMsg, MidiMsg};
+use std::sync::{Arc, RwLock};
+use tracing::log::*;
+
+/// the midi thru routes, shared between the midi inputs, the api & the sequencer.
+pub type Routes = Arc<RwLock<Vec<Route>>>;
+
+/// sends msg from the input dev_name to the outputs routed from it.
+pub fn route_midi(routes: &Routes, midi_out: &MidiOut, dev_name: &str, msg: &MidiMsg) {
+    let Ok(routes) = routes.read() else {
+        error!("the midi thru routes are poisoned");
+        return;
+    };
+
+    for route in routes.iter().filter(|route| route.from_dev == dev_name) {
+        if let Some(msg) = forward(route, msg)
+            && let Err(e) = midi_out.send((route.to_dev.clone(), msg))
+        {
+            error!(
+                "forwarding midi to \"{}\" failed with error: {e}",
+                route.to_dev
+            );
+        }
+    }
+}
+
+/// the message route should send for msg, if it should send anything.
+fn forward(route: &Route, msg: &MidiMsg) -> Option<MidiMsg> {
+    if route.drop.contains(&kind(msg)) {
+        return None;
+    }
+
+    let to_channel = |channel: Channel| route.to_channel.map_or(channel, Channel::from);
+    let from_channel = |channel: Channel| {
+        route
+            .from_channel
+            .is_none_or(|from| Channel::from(from) == channel)
+    };
+
+    match msg {
+        MidiMsg::ChannelVoice { channel, msg } if from_channel(*channel) => {
+            Some(MidiMsg::ChannelVoice {
+                channel: to_channel(*channel),
+                msg: transpose(*msg, route.transpose)?,
+            })
+        }
+        MidiMsg::ChannelMode { channel, msg } if from_channel(*channel) => {
+            Some(MidiMsg::ChannelMode {
+                channel: to_channel(*channel),
+                msg: *msg,
+            })
+        }
+        MidiMsg::ChannelVoice { .. } | MidiMsg::ChannelMode { .. } => None,
+        msg => Some(msg.clone()),
+    }
+}
+
+/// moves notes by semitones. notes moved out of midi's range are dropped.
+fn transpose(msg: ChannelVoiceMsg, semitones: i8) -> Option<ChannelVoiceMsg> {
+    let shift = |note: u8| {
+        note.checked_add_signed(semitones)
+            .filter(|note| *note <= 127)
+    };
+
+    Some(match msg {
+        ChannelVoiceMsg::NoteOn { note, velocity } => ChannelVoiceMsg::NoteOn {
+            note: shift(note)?,
+            velocity,
+        },
+        ChannelVoiceMsg::NoteOff { note, velocity } => ChannelVoiceMsg::NoteOff {
+            note: shift(note)?,
+            velocity,
+        },
+        ChannelVoiceMsg::HighResNoteOn { note, velocity } => ChannelVoiceMsg::HighResNoteOn {
+            note: shift(note)?,
+            velocity,
+        },
+        ChannelVoiceMsg::HighResNoteOff { note, velocity } => ChannelVoiceMsg::HighResNoteOff {
+            note: shift(note)?,
+            velocity,
+        },
+        ChannelVoiceMsg::PolyPressure { note, pressure } => ChannelVoiceMsg::PolyPressure {
+            note: shift(note)?,
+            pressure,
+        },
+        msg => msg,
+    })
+}
+
+fn kind(msg: &MidiMsg) -> MidiMsgKind {
+    match msg {
+        MidiMsg::ChannelVoice { msg, .. } => match msg {
+            ChannelVoiceMsg::NoteOn { .. }
+            | ChannelVoiceMsg::NoteOff { .. }
+            | ChannelVoiceMsg::HighResNoteOn { .. }
+            | ChannelVoiceMsg::HighResNoteOff { .. } => MidiMsgKind::Notes,
+            // includes the high resolution CCs & (N)RPNs
+            ChannelVoiceMsg::ControlChange { .. } => MidiMsgKind::CC,
+            ChannelVoiceMsg::PitchBend { .. } => MidiMsgKind::PitchBend,
+            ChannelVoiceMsg::ProgramChange { .. } => MidiMsgKind::ProgramChange,
+            ChannelVoiceMsg::PolyPressure { .. } | ChannelVoiceMsg::ChannelPressure { .. } => {
+                MidiMsgKind::Aftertouch
+            }
+        },
+        MidiMsg::ChannelMode { .. } => MidiMsgKind::CC,
+        _ => MidiMsgKind::System,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use midi_msg::{ControlChange, SystemRealTimeMsg};
+
+    fn voice(msg: ChannelVoiceMsg) -> MidiMsg {
+        MidiMsg::ChannelVoice {
+            channel: Channel::Ch1,
+            msg,
+        }
+    }
+
+    #[test]
+    fn kinds_match_the_filters() {
+        let cases = [
+            (
+                voice(ChannelVoiceMsg::HighResNoteOn {
+                    note: 60,
+                    velocity: 1000,
+                }),
+                MidiMsgKind::Notes,
+            ),
+            (
+                voice(ChannelVoiceMsg::ControlChange {
+                    control: ControlChange::CCHighRes {
+                        control1: 1,
+                        control2: 33,
+                        value: 1000,
+                    },
+                }),
+                MidiMsgKind::CC,
+            ),
+            (
+                voice(ChannelVoiceMsg::ControlChange {
+                    control: ControlChange::DataEntry(1000),
+                }),
+                MidiMsgKind::CC,
+            ),
+            (
+                voice(ChannelVoiceMsg::ChannelPressure { pressure: 10 }),
+                MidiMsgKind::Aftertouch,
+            ),
+            (
+                MidiMsg::SystemRealTime {
+                    msg: SystemRealTimeMsg::TimingClock,
+                },
+                MidiMsgKind::System,
+            ),
+        ];
+
+        for (msg, expected) in cases {
+            assert_eq!(kind(&msg), expected, "{msg:?}");
+        }
+    }
+
+    #[test]
+    fn dropped_kinds_are_not_forwarded() {
+        let route = Route::new(
+            "in".into(),
+            None,
+            "out".into(),
+            None,
+            0,
+            vec![MidiMsgKind::CC],
+        );
+        // (N)RPN values are sent as data entry CCs
+        let nrpn = voice(ChannelVoiceMsg::ControlChange {
+            control: ControlChange::DataEntry(1000),
+        });
+        let note = voice(ChannelVoiceMsg::NoteOn {
+            note: 60,
+            velocity: 100,
+        });
+
+        assert_eq!(forward(&route, &nrpn), None);
+        assert_eq!(forward(&route, &note), Some(note));
+    }
+}
@@ -0,0 +1,42 @@
+use crate::sequencer::step_pulses;
+use midi_daw_types::{MidiMsg, Sequence};
+
+/// how many pulses late step_i of sequence plays, from its swing & groove. a groove can pull a
+/// step back from its swing, but never to before the step itself.
+pub fn step_delay(sequence: &Sequence, step_i: usize, bpq: f64) -> f64 {
+    let swing = if step_i % 2 == 1 {
+        step_pulses(sequence.step_len, bpq) * sequence.swing.min(100) as f64 / 100.
+    } else {
+        0.
+    };
+    let offset = sequence
+        .groove
+        .as_ref()
+        .map_or(0, |groove| groove.step(step_i).offset);
+
+    (swing + offset as f64).max(0.)
+}
+
+/// msg, with the velocity of notes scaled by the sequence's groove.
+pub fn groove_msg(sequence: &Sequence, step_i: usize, msg: &MidiMsg) -> MidiMsg {
+    match (msg, &sequence.groove) {
+        (
+            MidiMsg::PlayNote {
+                note,
+                velocity,
+                duration,
+            },
+            Some(groove),
+        ) => {
+            let scale = groove.step(step_i).velocity as u32;
+
+            MidiMsg::PlayNote {
+                note: *note,
+                // a velocity of zero would be a note off.
+                velocity: (*velocity as u32 * scale / 100).clamp(1, 127) as u8,
+                duration: *duration,
+            }
+        }
+        _ => msg.clone(),
+    }
+}
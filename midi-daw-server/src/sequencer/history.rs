@@ -0,0 +1,180 @@
+use crate::sequencer::SequencerControlCmd;
+use midi_daw_types::{Sequence, SequenceName};
+use std::collections::VecDeque;
+
+/// the most edits that can be undone.
+const HISTORY_LEN: usize = 128;
+
+/// an edit to the sequences.
+#[derive(Clone, Debug)]
+pub enum Edit {
+    /// the sequence, name, changed from before to after. None means it didn't exist.
+    Changed {
+        name: SequenceName,
+        before: Option<Sequence>,
+        after: Option<Sequence>,
+    },
+    Renamed {
+        old_name: SequenceName,
+        new_name: SequenceName,
+    },
+    /// several edits made by one command, undone & redone together.
+    Many(Vec<Edit>),
+}
+
+impl Edit {
+    /// the edit that undoes this one.
+    fn inverse(self) -> Self {
+        match self {
+            Self::Changed {
+                name,
+                before,
+                after,
+            } => Self::Changed {
+                name,
+                before: after,
+                after: before,
+            },
+            Self::Renamed { old_name, new_name } => Self::Renamed {
+                old_name: new_name,
+                new_name: old_name,
+            },
+            Self::Many(edits) => Self::Many(edits.into_iter().rev().map(Self::inverse).collect()),
+        }
+    }
+
+    /// the sequences this edit changes.
+    pub fn sequence_names(&self) -> Vec<SequenceName> {
+        match self {
+            Self::Changed { name, .. } => vec![name.clone()],
+            Self::Renamed { old_name, new_name } => vec![old_name.clone(), new_name.clone()],
+            Self::Many(edits) => edits.iter().flat_map(Self::sequence_names).collect(),
+        }
+    }
+
+    /// the sequencer commands that make this edit.
+    pub fn into_cmds(self) -> Vec<SequencerControlCmd> {
+        match self {
+            Self::Changed {
+                after: Some(seq), ..
+            } => vec![SequencerControlCmd::RestoreSequence(seq)],
+            Self::Changed {
+                name, after: None, ..
+            } => vec![SequencerControlCmd::RmSequence { name }],
+            Self::Renamed { old_name, new_name } => {
+                vec![SequencerControlCmd::RenameSequence { old_name, new_name }]
+            }
+            Self::Many(edits) => edits.into_iter().flat_map(Self::into_cmds).collect(),
+        }
+    }
+}
+
+/// which stack the edit being replayed came from.
+#[derive(Clone, Copy, Debug)]
+enum Replay {
+    Undo,
+    Redo,
+}
+
+/// a bounded undo/redo history of edits to the sequences.
+#[derive(Default)]
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    /// true while an undo or redo is being made, so it isn't recorded as a new edit
+    replaying: bool,
+    /// the last undo or redo made
+    last_replay: Option<Replay>,
+}
+
+impl History {
+    pub fn set_replaying(&mut self, replaying: bool) {
+        self.replaying = replaying;
+    }
+
+    pub fn record(&mut self, edit: Edit) {
+        if self.replaying {
+            return;
+        }
+
+        self.undo.push_back(edit);
+        self.redo.clear();
+        self.last_replay = None;
+
+        if self.undo.len() > HISTORY_LEN {
+            self.undo.pop_front();
+        }
+    }
+
+    /// records the change to a sequence, if it changed.
+    pub fn record_change(
+        &mut self,
+        name: SequenceName,
+        before: Option<Sequence>,
+        after: Option<Sequence>,
+    ) {
+        if before != after {
+            self.record(Edit::Changed {
+                name,
+                before,
+                after,
+            });
+        }
+    }
+
+    /// records the changes to several sequences made by one command, so they're undone together.
+    pub fn record_changes(
+        &mut self,
+        changes: impl IntoIterator<Item = (SequenceName, Option<Sequence>, Option<Sequence>)>,
+    ) {
+        let edits: Vec<Edit> = changes
+            .into_iter()
+            .filter(|(_, before, after)| before != after)
+            .map(|(name, before, after)| Edit::Changed {
+                name,
+                before,
+                after,
+            })
+            .collect();
+
+        if !edits.is_empty() {
+            self.record(Edit::Many(edits));
+        }
+    }
+
+    /// the edit that undoes the last edit.
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop_back()?;
+        self.redo.push(edit.clone());
+        self.last_replay = Some(Replay::Undo);
+
+        Some(edit.inverse())
+    }
+
+    /// the last edit that was undone.
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.undo.push_back(edit.clone());
+        self.last_replay = Some(Replay::Redo);
+
+        Some(edit)
+    }
+
+    /// puts the edit from the last undo or redo back where it came from, for when it couldn't be
+    /// made.
+    pub fn replay_failed(&mut self) {
+        match self.last_replay.take() {
+            Some(Replay::Undo) => {
+                if let Some(edit) = self.redo.pop() {
+                    self.undo.push_back(edit);
+                }
+            }
+            Some(Replay::Redo) => {
+                if let Some(edit) = self.undo.pop_back() {
+                    self.redo.push(edit);
+                }
+            }
+            None => {}
+        }
+    }
+}
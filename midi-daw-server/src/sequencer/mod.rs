@@ -1,29 +1,66 @@
-use std::time::Duration;
-
 use actix::dev::OneshotSender;
-use async_std::{
-    fs::{File, create_dir_all, read_dir, remove_file},
-    io::{BufReader, ReadExt, WriteExt},
-};
-use crossbeam::channel::Receiver;
-use futures_lite::stream::StreamExt;
+use crossbeam::channel::{Receiver, Select, Sender};
 use fx_hash::FxHashMap;
-use http_body_util::Full;
-use hyper::{Method, Request, body::Bytes};
-use hyper_util::client::legacy::Client;
-use hyperlocal::{UnixClientExt, UnixConnector, Uri};
 use midi_daw_types::{
-    BPQ, MidiChannel, MidiMsg, MidiReqBody, MsgFromServer, NoteDuration, Sequence, SequenceName,
-    Tempo, UDS_SERVER_PATH,
+    Arrangement, BPQ, ClockSource, Groove, LaunchQuant, MidiChannel, MidiMsg, MidiReqBody,
+    MsgFromServer, NoteDuration, Project, RecordMode, Scene, Sequence, SequenceName,
+    SequencerError, StartAutomationBody, Step, Tempo,
 };
-use tokio::spawn;
+use std::{collections::VecDeque, time::Duration};
+use tokio::sync::oneshot;
 use tracing::*;
 use uuid::Uuid;
-use xdg::BaseDirectories;
 
-use crate::{midi::out::unwrap_rw_lock, server::message_bus::MbServerHandle};
+use crate::{
+    clock::{Clock, MonotonicClock, PulseScheduler, pulse_len},
+    midi::{
+        MidiDev, VirtualDevs,
+        automation::{AutomationCmd, make_automation},
+        clock_in::{ClockIn, ClockInEvent, MAX_TICK_GAP},
+        clock_out::Transport,
+        out::{Pulse, unwrap_rw_lock},
+        thru::Routes,
+    },
+    sequencer::{
+        groove::{groove_msg, step_delay},
+        history::{Edit, History},
+        record::Recorder,
+        smf::to_smf,
+        storage::{
+            PROJECTS_DIR, list_saved, load_project, load_sequence, rm_saved, save_project,
+            save_sequence,
+        },
+    },
+    server::message_bus::MbServerHandle,
+};
+
+mod groove;
+mod history;
+mod record;
+mod smf;
+mod storage;
+#[cfg(test)]
+mod tests;
 
 pub type AllSequences = FxHashMap<SequenceName, Sequence>;
+/// sends back whether a control command was carried out, or why it wasn't.
+pub type CmdResponder = OneshotSender<Result<(), SequencerError>>;
+/// a control command & the responder to send its result to, (None if nothing is waiting on it).
+pub type SequencerReq = (SequencerControlCmd, Option<CmdResponder>);
+/// how many control commands can be waiting on the sequencer before the server starts turning
+/// requests away.
+pub const CONTROL_QUEUE_LEN: usize = 256;
+/// slack for float error when working out which pulse a step lands on, (ie: triplets).
+const STEP_EPSILON: f64 = 1e-6;
+
+/// where the sequencer is in the arrangement.
+struct ArrangementPos {
+    /// the section being played
+    index: usize,
+    /// the pulse the section starts on
+    start: f64,
+    started: bool,
+}
 
 // #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum SequencerControlCmd {
@@ -34,6 +71,25 @@ pub enum SequencerControlCmd {
     GetSequences {
         responder: OneshotSender<Vec<String>>,
     },
+    /// switches between the internal clock & following an external midi clock
+    SetClockSource(ClockSource),
+    GetClockSource {
+        responder: OneshotSender<ClockSource>,
+    },
+    /// sets when queued sequences start & stop, for requests that don't set their own
+    SetLaunchQuant(LaunchQuant),
+    GetLaunchQuant {
+        responder: OneshotSender<LaunchQuant>,
+    },
+    /// arms a sequence to record the notes played on a midi input
+    Record {
+        sequence: SequenceName,
+        midi_dev: String,
+        mode: RecordMode,
+        /// the number of bars to wait before recording
+        count_in: usize,
+    },
+    StopRecording,
     NewSequence {
         name: Option<SequenceName>,
         midi_dev: Option<String>,
@@ -56,11 +112,38 @@ pub enum SequencerControlCmd {
     RmSequence {
         name: SequenceName,
     },
-    Play(Vec<SequenceName>),
+    /// starts sequences, quantized by the LaunchQuant or the global launch quantization if None
+    Play(Vec<SequenceName>, Option<LaunchQuant>),
     PlayAll,
     Stop(Vec<SequenceName>),
     StopAll,
-    QueueStop(Vec<SequenceName>),
+    /// stops sequences, quantized like Play
+    QueueStop(Vec<SequenceName>, Option<LaunchQuant>),
+    /// adds a scene, replacing any with the same name
+    SetScene(Scene),
+    RmScene {
+        name: String,
+    },
+    GetScenes {
+        responder: OneshotSender<Vec<Scene>>,
+    },
+    /// starts a scene's sequences & stops every other sequence, quantized like Play
+    LaunchScene {
+        name: String,
+        quant: Option<LaunchQuant>,
+    },
+    /// replaces the arrangement
+    SetArrangement(Arrangement),
+    GetArrangement {
+        responder: OneshotSender<Arrangement>,
+    },
+    /// plays through the arrangement starting from the section at index from, (on the next launch
+    /// boundary)
+    PlayArrangement {
+        from: usize,
+    },
+    /// stops following the arrangement, the sequences playing keep playing
+    StopArrangement,
     Pause(Vec<SequenceName>),
     PauseAll,
     AddNote {
@@ -90,6 +173,21 @@ pub enum SequencerControlCmd {
         sequence: SequenceName,
         amt: isize,
     },
+    /// sets how long each step of a sequence lasts.
+    SetStepLen {
+        sequence: SequenceName,
+        step_len: NoteDuration,
+    },
+    /// sets how late every other step of a sequence plays, as a percentage of a step.
+    SetSwing {
+        sequence: SequenceName,
+        swing: u8,
+    },
+    /// sets (or with None removes) a sequence's groove template.
+    SetGroove {
+        sequence: SequenceName,
+        groove: Option<Groove>,
+    },
     /// saves a sequence to disk
     SaveSequence {
         /// the sequence name to save
@@ -98,7 +196,7 @@ pub enum SequencerControlCmd {
     /// lists sequences that have been saved to disk that are not a part of a project
     ListSavedSequences {
         /// will send back the base file names without the parent directory
-        responder: OneshotSender<Vec<String>>,
+        responder: OneshotSender<Result<Vec<String>, SequencerError>>,
     },
     /// loads a sequence from disk
     LoadSequence {
@@ -110,20 +208,40 @@ pub enum SequencerControlCmd {
         /// Sequence name to rm
         sequence: SequenceName,
     },
-    /// saves all sequences into a sub folder of the data dir. the base file name will be based on the sequences name
+    /// saves the sequences & the global state, (tempo, devices, automations, etc), into a sub folder
+    /// of the data dir. the base file name will be the project name
     SaveProject {
         project_name: String,
     },
     /// lists only the projects that have been saved (not their sequences)
     ListSavedProjects {
         /// will send back the base file names without the parent directory
-        responder: OneshotSender<Vec<String>>,
+        responder: OneshotSender<Result<Vec<String>, SequencerError>>,
     },
-    /// loads a Project and its sequences from disk
+    /// loads a Project and its sequences from disk, replacing the open sequences, scenes,
+    /// arrangement, routes & automations. playback is stopped first.
     LoadSavedProject {
         /// the sequence name to load
         project_name: String,
     },
+    /// adds sequences read from a standard midi file, replacing any with the same name
+    ImportSequences(Vec<Sequence>),
+    /// puts back a sequence from the edit history, replacing any with the same name
+    RestoreSequence(Sequence),
+    /// undoes the last edit to the sequences
+    Undo,
+    /// redoes the last undone edit
+    Redo,
+    /// renders a sequence to a standard midi file
+    ExportSmf {
+        sequence: SequenceName,
+        responder: OneshotSender<Result<Vec<u8>, SequencerError>>,
+    },
+    /// renders every sequence to a standard midi file, one track per sequence
+    ExportProjectSmf {
+        project_name: String,
+        responder: OneshotSender<Result<Vec<u8>, SequencerError>>,
+    },
     /// rm a project from storage.
     RmSavedProject {
         /// Sequence name to rm
@@ -131,137 +249,294 @@ pub enum SequencerControlCmd {
     },
 }
 
+impl SequencerControlCmd {
+    /// the sequences this command needs to exist.
+    fn required_sequences(&self) -> &[SequenceName] {
+        match self {
+            Self::Play(names, _)
+            | Self::Stop(names)
+            | Self::QueueStop(names, _)
+            | Self::Pause(names) => names,
+            Self::Record { sequence, .. }
+            | Self::AddNote { sequence, .. }
+            | Self::RmNote { sequence, .. }
+            | Self::AddCmd { sequence, .. }
+            | Self::RmCmd { sequence, .. }
+            | Self::ChangeLenBy { sequence, .. }
+            | Self::SetStepLen { sequence, .. }
+            | Self::SetSwing { sequence, .. }
+            | Self::SetGroove { sequence, .. }
+            | Self::SaveSequence { sequence }
+            | Self::SetSequenceDev { name: sequence, .. }
+            | Self::SetSequenceChannel { name: sequence, .. }
+            | Self::RenameSequence {
+                old_name: sequence, ..
+            }
+            | Self::RmSequence { name: sequence } => std::slice::from_ref(sequence),
+            _ => &[],
+        }
+    }
+
+    /// the sequence this command edits, if it's an edit that can be undone.
+    fn edited_sequence(&self) -> Option<SequenceName> {
+        match self {
+            Self::AddNote { sequence, .. }
+            | Self::RmNote { sequence, .. }
+            | Self::AddCmd { sequence, .. }
+            | Self::RmCmd { sequence, .. }
+            | Self::ChangeLenBy { sequence, .. } => Some(sequence.clone()),
+            Self::RmSequence { name } => Some(name.clone()),
+            Self::NewSequence { name, .. } => {
+                Some(name.clone().unwrap_or(Sequence::default().name))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 #[tokio::main]
 pub async fn sequencer_start(
     tempo: Tempo,
     bpq: BPQ,
-    controls: Receiver<SequencerControlCmd>,
+    controls: Receiver<SequencerReq>,
     mb_sender: MbServerHandle,
+    automation_cmds: Sender<AutomationCmd>,
+    midi_reqs: Sender<MidiReqBody>,
+    transport: Sender<Transport>,
+    pulses: Sender<Pulse>,
+    midi_in: Receiver<(String, midi_msg::MidiMsg, Duration)>,
+    routes: Routes,
+    new_dev_tx: Sender<MidiDev>,
+    virtual_devs: VirtualDevs,
+    clock: MonotonicClock,
 ) {
-    // let url = Uri::new("/tmp/hyperlocal.sock", "/").into();
-
-    let mk_timer = || {
-        // calculate sleep_time based on BPQ & tempo.
-        let (tempo, beats) = (unwrap_rw_lock(&tempo, 99.), unwrap_rw_lock(&bpq, 24.));
-        let sleep_time = Duration::from_secs_f64((60.0 / tempo) / beats);
-
-        move || {
-            std::thread::sleep(sleep_time);
-        }
-    };
-
-    // start a sleep thread to sleep for sleep_time.
-    let mut sleep_thread = std::thread::spawn(mk_timer());
+    let mut scheduler = PulseScheduler::new(
+        clock,
+        unwrap_rw_lock(&tempo, 99.),
+        unwrap_rw_lock(&bpq, 24.),
+    );
     let mut counter = 0.;
+    // the sync pulses sent to midi out, (unlike the counter this never resets).
+    let mut sync_pulses: u64 = 0;
 
     let mut sequences: AllSequences = FxHashMap::default();
     let mut queued_sequences: Vec<SequenceName> = Vec::default();
     let mut queued_stop_sequences: Vec<SequenceName> = Vec::default();
     let mut playing_sequences: Vec<SequenceName> = Vec::default();
-    let mut jh_s = Vec::default();
+    // the step each paused sequence will resume from
+    let mut paused_sequences: FxHashMap<SequenceName, usize> = FxHashMap::default();
+    // how far ahead of the global step counter each resumed sequence is
+    let mut step_offsets: FxHashMap<SequenceName, usize> = FxHashMap::default();
+    // the pulse each queued sequence starts or stops on
+    let mut launch_pulses: FxHashMap<SequenceName, f64> = FxHashMap::default();
+    let mut launch_quant = LaunchQuant::default();
+    let mut scenes: FxHashMap<String, Scene> = FxHashMap::default();
+    // the pulse the tempo changes on & the tempo it changes to, (set by scenes & arrangements)
+    let mut pending_tempo: Option<(f64, f64)> = None;
+    let mut arrangement = Arrangement::default();
+    let mut arrangement_pos: Option<ArrangementPos> = None;
+    // midi messages waiting to be played, with the pulse they play on & their sequence
+    let mut scheduled_messages: Vec<(f64, SequenceName, MidiReqBody)> = Vec::new();
+    let mut history = History::default();
+    // the commands that make an undo or redo
+    let mut history_cmds: VecDeque<SequencerControlCmd> = VecDeque::new();
     let conn = uuid::Uuid::new_v4();
+    // true while the devices following the midi clock should be playing. while following an
+    // external clock, it follows that clock's transport instead of what's playing.
+    let mut transport_running = false;
+    let mut clock_source = ClockSource::default();
+    let mut clock_in = ClockIn::default();
+    // true if the sequencer was following the external clock last time round
+    let mut was_following = false;
+    let mut recorder: Option<Recorder> = None;
 
     loop {
-        if sleep_thread.is_finished() {
-            sleep_thread = std::thread::spawn(mk_timer());
+        if transport_running && playing_sequences.is_empty() && clock_source.is_internal() {
+            transport_running = false;
+            send_transport(&transport, Transport::Stop);
+        }
 
-            if (counter % (unwrap_rw_lock(&bpq, 24.) / 4.)) == 0.0 {
-                let i = counter / (unwrap_rw_lock(&bpq, 24.) / 4.);
-                // info!("i = {i}");
-                // info!("i % 16 = {}", i as usize % 16);
+        // while the external clock is stopped or gone, the internal clock keeps midi out's time,
+        // so notes still end, automations still step & the api's waits still return.
+        let following = !clock_source.is_internal() && clock_in.is_following(clock.now());
 
-                if i % 16. == 0. || playing_sequences.is_empty() {
-                    queued_sequences.iter().for_each(|name| {
-                        let msg = MsgFromServer::SequenceStarted {
-                            sequence_name: name.clone(),
-                        };
-                        send_msg(&mb_sender, &conn, msg);
-                    });
+        if was_following && !following {
+            scheduler.resync();
+        }
 
-                    playing_sequences.append(&mut queued_sequences);
-                }
+        was_following = following;
 
-                playing_sequences.retain(|name| {
-                    if let Some(sequence) = sequences.get(name) {
-                        if i as usize % sequence.steps.len() == 0 {
-                            let res = queued_stop_sequences.contains(name);
-                            // queued_stop_sequences.retain(|stop_name| stop_name != name);
+        // sleep until the next pulse is due, or until a control or midi message arrives.
+        let time_until_due = if following {
+            // external pulses only come with midi in, but wake up to notice the clock going away.
+            (!clock_in.has_pulse()).then_some(MAX_TICK_GAP)
+        } else {
+            scheduler.time_until_due()
+        };
+
+        if history_cmds.is_empty()
+            && let Some(timeout) = time_until_due
+        {
+            let mut sel = Select::new();
+            sel.recv(&controls);
+            sel.recv(&midi_in);
+            let _ = sel.ready_timeout(timeout);
+        }
 
-                            if res {
-                                let msg = MsgFromServer::SequenceStoped {
-                                    sequence_name: name.clone(),
-                                    step_n: i as usize,
-                                };
+        while let Ok((dev_name, msg, received)) = midi_in.try_recv() {
+            let beats = unwrap_rw_lock(&bpq, 24.);
+
+            // record played notes
+            if let Some(recorder) = recorder.as_mut()
+                && recorder.midi_dev == dev_name
+                && let Some(seq) = sequences.get_mut(&recorder.sequence)
+            {
+                let offset = step_offsets.get(&recorder.sequence).copied().unwrap_or(0);
+                let pulse = pulse_pos(
+                    counter,
+                    &scheduler,
+                    &clock_source,
+                    unwrap_rw_lock(&tempo, 99.),
+                    beats,
+                );
+
+                recorder.handle(&msg, seq, offset, pulse, beats);
+            }
 
-                                send_msg(&mb_sender, &conn, msg);
-                            }
+            // follow the external clock
+            let ClockSource::External { midi_dev } = &clock_source else {
+                continue;
+            };
 
-                            !res
-                        } else {
-                            true
-                        }
-                    } else {
-                        true
+            if &dev_name != midi_dev {
+                continue;
+            }
+
+            match clock_in.handle(&msg, beats, received) {
+                Some(ClockInEvent::Tick) => send_transport(&transport, Transport::Clock),
+                Some(ClockInEvent::Start) => {
+                    counter = 0.;
+                    step_offsets.clear();
+                    // the song restarted, so everything queued is due.
+                    launch_pulses.clear();
+
+                    if let Some(pos) = arrangement_pos.as_mut() {
+                        pos.start = 0.;
                     }
-                });
-                queued_stop_sequences.retain(|stop_name| playing_sequences.contains(stop_name));
-
-                // send midi messages from playing sequences
-                let play_messages: Vec<MidiReqBody> = playing_sequences
-                    .iter()
-                    .filter_map(|name| {
-                        if let Some(sequence) = sequences.get(name) {
-                            debug!(
-                                "sequence, {}, has {} steps",
-                                sequence.name,
-                                sequence.steps.len()
-                            );
 
-                            let msgs = sequence.steps[i as usize % sequence.steps.len()]
-                                .iter()
-                                .map(|msg| {
-                                    MidiReqBody::new(
-                                        sequence.midi_dev.clone(),
-                                        sequence.channel,
-                                        msg.clone(),
-                                    )
-                                });
+                    send_msg(&mb_sender, &conn, MsgFromServer::SyncPulseReset());
+                    sync_reset_automations(&automation_cmds);
+                    transport_running = true;
+                    send_transport(&transport, Transport::Start);
+                }
+                Some(ClockInEvent::Stop) => {
+                    transport_running = false;
+                    send_transport(&transport, Transport::Stop);
+                    send_msg(&mb_sender, &conn, MsgFromServer::ClockStopped());
+                }
+                Some(ClockInEvent::Continue) => {
+                    let step_n = (counter / (beats / 4.)) as usize;
+
+                    transport_running = true;
+                    send_transport(
+                        &transport,
+                        Transport::Continue {
+                            position: step_n.min(0x3FFF) as u16,
+                        },
+                    );
+                    send_msg(&mb_sender, &conn, MsgFromServer::ClockContinued { step_n });
+                }
+                Some(ClockInEvent::SongPosition(position)) => {
+                    counter = position as f64 * (beats / 4.);
+                }
+                None => {}
+            }
 
-                            if msgs.len() > 0 { Some(msgs) } else { None }
-                        } else {
-                            None
-                        }
-                    })
-                    .flatten()
-                    .collect();
-
-                if !play_messages.is_empty() {
-                    let jh = spawn(async move {
-                        trace!("playing {} notes.", play_messages.len());
-                        let url = Uri::new(UDS_SERVER_PATH, "/batch-midi");
-                        let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
-                        let req = Request::builder()
-                            .method(Method::POST)
-                            .uri(url)
-                            .header("content-type", "application/json")
-                            .body(Full::new(Bytes::from(
-                                serde_json::json!(play_messages).to_string(),
-                            )))
-                            .unwrap();
-
-                        match client.request(req).await {
-                            Ok(_res) => {}
-                            Err(e) => error!("failed to play a note, got error: {e}"),
-                        }
-                    });
-
-                    jh_s.push(jh);
-
-                    jh_s.retain(|jh| !jh.is_finished());
-                    // break;
+            // keep the tempo in sync so automations & note lengths follow the external clock.
+            if let Some(ext_tempo) = clock_in.tempo()
+                && ext_tempo != unwrap_rw_lock(&tempo, 99.)
+                && let Ok(mut tempo) = tempo.write()
+            {
+                *tempo = ext_tempo;
+            }
+        }
+
+        let pulse_due = if following {
+            clock_in.take_pulse()
+        } else {
+            let is_due = scheduler.is_due();
+
+            if is_due {
+                scheduler.advance();
+                scheduler.set_tempo(unwrap_rw_lock(&tempo, 99.), unwrap_rw_lock(&bpq, 24.));
+            }
+
+            is_due
+        };
+
+        // the external clock isn't moving, so the sequences wait for it. only midi out is kept in
+        // time, (its midi clock still comes from the external clock).
+        if pulse_due && !clock_source.is_internal() && !following {
+            let pulse = Pulse {
+                n: sync_pulses,
+                external: true,
+            };
+
+            if let Err(e) = pulses.send(pulse) {
+                error!("sending a sync pulse to midi out failed with error: {e}");
+            }
+
+            sync_pulses += 1;
+
+            continue;
+        }
+
+        // the sequencer keeps counting steps during a count-in & while following the arrangement,
+        // even if nothing is playing.
+        let keep_counting =
+            recorder.as_ref().is_some_and(|rec| !rec.is_started()) || arrangement_pos.is_some();
+
+        if pulse_due {
+            let pulse = Pulse {
+                n: sync_pulses,
+                external: !clock_source.is_internal(),
+            };
+
+            if let Err(e) = pulses.send(pulse) {
+                error!("sending a sync pulse to midi out failed with error: {e}");
+            }
+
+            sync_pulses += 1;
+
+            if (counter % (unwrap_rw_lock(&bpq, 24.) / 4.)) == 0.0 {
+                let i = counter / (unwrap_rw_lock(&bpq, 24.) / 4.);
+                // info!("i = {i}");
+                // info!("i % 16 = {}", i as usize % 16);
+
+                // the count-in is over, so start recording & playing the armed sequence.
+                if let Some(recorder) = recorder.as_mut()
+                    && recorder.step(i as usize)
+                {
+                    let name = recorder.sequence.clone();
+
+                    if !playing_sequences.contains(&name) && !queued_sequences.contains(&name) {
+                        paused_sequences.remove(&name);
+                        step_offsets.remove(&name);
+                        launch_pulses.insert(name.clone(), counter);
+                        queued_sequences.push(name.clone());
+                    }
+
+                    send_msg(
+                        &mb_sender,
+                        &conn,
+                        MsgFromServer::RecordingStarted {
+                            sequence_name: name,
+                        },
+                    );
                 }
 
-                if !(playing_sequences.is_empty() && queued_sequences.is_empty()) {
+                if !(playing_sequences.is_empty() && queued_sequences.is_empty()) || keep_counting {
                     // mb_sender.send_binary(conn, i.to_ne_bytes().to_vec().into());
                     let msg_struct = MsgFromServer::Step {
                         pulse_count: counter as usize,
@@ -274,7 +549,206 @@ pub async fn sequencer_start(
                 }
             }
 
-            if !(playing_sequences.is_empty() && queued_sequences.is_empty()) {
+            // play through the arrangement
+            let mut arrangement_over = false;
+
+            if let Some(pos) = arrangement_pos.as_mut() {
+                let beats = unwrap_rw_lock(&bpq, 24.);
+                let bars = arrangement
+                    .sections
+                    .get(pos.index)
+                    .map_or(1, |section| section.bars.max(1));
+                let end = pos.start + bars as f64 * 4. * beats;
+
+                if pos.started && end <= counter + STEP_EPSILON {
+                    let next = arrangement
+                        .next_section(pos.index)
+                        .and_then(|index| Some((index, arrangement.sections.get(index)?)));
+                    let names = next.map_or(Vec::new(), |(_, section)| section.sequences.clone());
+
+                    launch_group(
+                        &names,
+                        LaunchQuant::Immediate(),
+                        counter,
+                        beats,
+                        &sequences,
+                        &playing_sequences,
+                        &mut queued_sequences,
+                        &mut queued_stop_sequences,
+                        &mut paused_sequences,
+                        &mut step_offsets,
+                        &mut launch_pulses,
+                        &mb_sender,
+                        &conn,
+                    );
+
+                    if let Some((index, section)) = next {
+                        pending_tempo = section.tempo.map(|tempo| (counter, tempo));
+                        *pos = ArrangementPos {
+                            index,
+                            start: counter,
+                            started: false,
+                        };
+                    } else {
+                        arrangement_over = true;
+                        send_msg(&mb_sender, &conn, MsgFromServer::ArrangementEnded());
+                    }
+                }
+
+                if !pos.started && pos.start <= counter + STEP_EPSILON {
+                    pos.started = true;
+
+                    let msg = MsgFromServer::ArrangementSection { index: pos.index };
+                    send_msg(&mb_sender, &conn, msg);
+                }
+            }
+
+            if arrangement_over {
+                arrangement_pos = None;
+            }
+
+            if let Some((pulse, new_tempo)) = pending_tempo
+                && pulse <= counter + STEP_EPSILON
+            {
+                pending_tempo = None;
+
+                match tempo.write() {
+                    Ok(mut tempo) => *tempo = new_tempo,
+                    Err(e) => error!("setting the tempo failed with error, {e}"),
+                }
+            }
+
+            // start the queued sequences that are due.
+            let (starting, waiting): (Vec<_>, Vec<_>) = queued_sequences
+                .drain(..)
+                .partition(|name| launch_due(&launch_pulses, name, counter));
+            queued_sequences = waiting;
+            // sequences resumed while they were queued are already playing.
+            let starting: Vec<_> = starting
+                .into_iter()
+                .filter(|name| !playing_sequences.contains(name))
+                .collect();
+
+            if !starting.is_empty() {
+                // starting from silence, so line the tempo synced automations up with the bar.
+                if playing_sequences.is_empty() {
+                    sync_reset_automations(&automation_cmds);
+
+                    if !transport_running && clock_source.is_internal() {
+                        transport_running = true;
+                        send_transport(&transport, Transport::Start);
+                    }
+                }
+
+                starting.iter().for_each(|name| {
+                    launch_pulses.remove(name);
+
+                    let msg = MsgFromServer::SequenceStarted {
+                        sequence_name: name.clone(),
+                    };
+                    send_msg(&mb_sender, &conn, msg);
+                });
+
+                playing_sequences.extend(starting);
+            }
+
+            let beats = unwrap_rw_lock(&bpq, 24.);
+            // the step of each playing sequence that starts on this pulse, (sequences step at
+            // their own rate so not all of them have a step on every pulse).
+            let seq_steps: FxHashMap<SequenceName, usize> = playing_sequences
+                .iter()
+                .filter_map(|name| {
+                    let sequence = sequences.get(name)?;
+                    let step_n = seq_step(counter, sequence.step_len, beats)?;
+
+                    (!sequence.steps.is_empty()).then(|| {
+                        let step_i = local_step(&step_offsets, name, step_n, sequence.steps.len());
+
+                        (name.clone(), step_i)
+                    })
+                })
+                .collect();
+
+            // let the frontends highlight each sequence's own step.
+            seq_steps.iter().for_each(|(name, step_i)| {
+                let Some(sequence) = sequences.get(name) else {
+                    return;
+                };
+                let msg = MsgFromServer::SequenceStep {
+                    sequence_name: name.clone(),
+                    step_n: *step_i,
+                    step_len: sequence.step_len,
+                };
+
+                send_msg(&mb_sender, &conn, msg);
+            });
+
+            playing_sequences.retain(|name| {
+                let stop = queued_stop_sequences.contains(name)
+                    && launch_due(&launch_pulses, name, counter);
+
+                if stop {
+                    launch_pulses.remove(name);
+
+                    let msg = MsgFromServer::SequenceStoped {
+                        sequence_name: name.clone(),
+                        step_n: (counter / (beats / 4.)) as usize,
+                    };
+
+                    send_msg(&mb_sender, &conn, msg);
+                }
+
+                !stop
+            });
+            queued_stop_sequences.retain(|stop_name| playing_sequences.contains(stop_name));
+
+            // clear the steps being recorded over
+            if let Some(recorder) = recorder.as_mut()
+                && recorder.is_started()
+                && playing_sequences.contains(&recorder.sequence)
+                && let Some(step_i) = seq_steps.get(&recorder.sequence)
+                && let Some(seq) = sequences.get_mut(&recorder.sequence)
+            {
+                recorder.clear_step(seq, *step_i);
+            }
+
+            // schedule the midi messages from playing sequences, late if they swing.
+            playing_sequences.iter().for_each(|name| {
+                let (Some(sequence), Some(step_i)) = (sequences.get(name), seq_steps.get(name))
+                else {
+                    return;
+                };
+                let pulse = counter + step_delay(sequence, *step_i, beats);
+
+                sequence.steps[*step_i].iter().for_each(|msg| {
+                    let msg = groove_msg(sequence, *step_i, msg);
+                    let body = MidiReqBody::new(sequence.midi_dev.clone(), sequence.channel, msg);
+
+                    scheduled_messages.push((pulse, name.clone(), body));
+                });
+            });
+
+            // send the messages that are due, (messages from stopped sequences are dropped).
+            scheduled_messages.retain(|(_, name, _)| playing_sequences.contains(name));
+            let (due, later): (Vec<_>, Vec<_>) = scheduled_messages
+                .drain(..)
+                .partition(|(pulse, _, _)| *pulse <= counter + STEP_EPSILON);
+            scheduled_messages = later;
+
+            let mut play_messages: Vec<MidiReqBody> =
+                due.into_iter().map(|(_, _, body)| body).collect();
+            // send the per step commands, (ie: CCs), before the notes they effect.
+            play_messages.sort_by_key(|body| matches!(body.msg, MidiMsg::PlayNote { .. }));
+
+            trace!("playing {} midi messages.", play_messages.len());
+
+            for body in play_messages {
+                if let Err(e) = midi_reqs.send(body) {
+                    error!("failed to play a note, got error: {e}");
+                }
+            }
+
+            if !(playing_sequences.is_empty() && queued_sequences.is_empty()) || keep_counting {
                 let i = counter / (unwrap_rw_lock(&bpq, 24.) / 4.);
                 let (after_step, before_step) = (i.floor() as usize, i.ceil() as usize);
 
@@ -294,9 +768,35 @@ pub async fn sequencer_start(
                 send_msg(&mb_sender, &conn, msg);
             }
         } else {
-            while let Ok(msg) = controls.try_recv() {
+            while let Some((msg, responder, from_history)) = history_cmds
+                .pop_front()
+                .map(|cmd| (cmd, None, true))
+                .or_else(|| {
+                    controls
+                        .try_recv()
+                        .ok()
+                        .map(|(cmd, responder)| (cmd, responder, false))
+                })
+            {
+                history.set_replaying(from_history);
+
+                let edited = msg.edited_sequence();
+                let before = edited
+                    .as_ref()
+                    .and_then(|name| sequences.get(name).cloned());
+
+                if let Err(e) = check_sequences(&sequences, msg.required_sequences()) {
+                    if from_history {
+                        history_cmds.clear();
+                        history.replay_failed();
+                    }
+
+                    respond(responder, Err(e));
+                    continue;
+                }
+
                 // do msg thing
-                match msg {
+                let res = match msg {
                     SequencerControlCmd::GetSequence {
                         sequence,
                         responder,
@@ -306,6 +806,111 @@ pub async fn sequencer_start(
                         {
                             error!("sending sequence failed with error: {e:?}");
                         }
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::SetClockSource(source) => {
+                        info!("clock source set to {source:?}");
+
+                        // start the internal clock from now, rather then catching up on the
+                        // pulses missed while following the external clock.
+                        if source == ClockSource::Internal() && clock_source != source {
+                            scheduler = PulseScheduler::new(
+                                clock,
+                                unwrap_rw_lock(&tempo, 99.),
+                                unwrap_rw_lock(&bpq, 24.),
+                            );
+                        }
+
+                        clock_in = ClockIn::default();
+                        clock_source = source;
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::GetClockSource { responder } => {
+                        if let Err(e) = responder.send(clock_source.clone()) {
+                            error!("sending clock source failed with error: {e:?}");
+                        }
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::SetLaunchQuant(quant) => {
+                        info!("launch quantization set to {quant:?}");
+                        launch_quant = quant;
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::GetLaunchQuant { responder } => {
+                        if let Err(e) = responder.send(launch_quant) {
+                            error!("sending launch quantization failed with error: {e:?}");
+                        }
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::Record {
+                        sequence,
+                        midi_dev,
+                        mode,
+                        count_in,
+                    } => {
+                        let beats = unwrap_rw_lock(&bpq, 24.);
+                        let pulse = pulse_pos(
+                            counter,
+                            &scheduler,
+                            &clock_source,
+                            unwrap_rw_lock(&tempo, 99.),
+                            beats,
+                        );
+                        stop_recording(
+                            &mut recorder,
+                            &mut sequences,
+                            &step_offsets,
+                            pulse,
+                            beats,
+                            &mb_sender,
+                            &conn,
+                        );
+
+                        // recording starts on a bar, after the count-in.
+                        let next_step = (counter / (beats / 4.)).ceil() as usize;
+                        let idle = playing_sequences.is_empty() && queued_sequences.is_empty();
+                        let next_bar = if idle { 0 } else { next_step.div_ceil(16) * 16 };
+                        let start_step = next_bar + count_in * 16;
+
+                        info!(
+                            "recording \"{midi_dev}\" into \"{sequence}\" from step {start_step}"
+                        );
+
+                        let msg = MsgFromServer::RecordingWillStart {
+                            sequence_name: sequence.clone(),
+                            after_steps: start_step - if idle { 0 } else { next_step },
+                        };
+
+                        send_msg(&mb_sender, &conn, msg);
+                        recorder = Some(Recorder::new(sequence, midi_dev, mode, start_step));
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::StopRecording => {
+                        let beats = unwrap_rw_lock(&bpq, 24.);
+                        let pulse = pulse_pos(
+                            counter,
+                            &scheduler,
+                            &clock_source,
+                            unwrap_rw_lock(&tempo, 99.),
+                            beats,
+                        );
+                        stop_recording(
+                            &mut recorder,
+                            &mut sequences,
+                            &step_offsets,
+                            pulse,
+                            beats,
+                            &mb_sender,
+                            &conn,
+                        );
+
+                        Ok(())
                     }
                     SequencerControlCmd::GetSequences { responder } => {
                         if let Err(e) =
@@ -313,6 +918,8 @@ pub async fn sequencer_start(
                         {
                             error!("sending sequence names failed with error: {e:?}");
                         }
+
+                        Ok(())
                     }
                     SequencerControlCmd::NewSequence {
                         name,
@@ -334,32 +941,80 @@ pub async fn sequencer_start(
                         }
 
                         sequences.insert(seq.name.clone(), seq);
+
+                        Ok(())
                     }
                     SequencerControlCmd::SetSequenceDev { name, midi_dev } => {
-                        if let Some(seq) = sequences.get_mut(&name) {
-                            seq.midi_dev = midi_dev;
-                        }
+                        get_sequence(&mut sequences, &name).map(|seq| seq.midi_dev = midi_dev)
                     }
                     SequencerControlCmd::SetSequenceChannel { name, channel } => {
-                        if let Some(seq) = sequences.get_mut(&name) {
-                            seq.channel = channel;
-                        }
+                        get_sequence(&mut sequences, &name).map(|seq| seq.channel = channel)
                     }
                     SequencerControlCmd::RenameSequence { old_name, new_name } => {
-                        if let Some(mut seq) = sequences.remove(&old_name) {
-                            seq.name = new_name.clone();
+                        if new_name != old_name && sequences.contains_key(&new_name) {
+                            Err(SequencerError::NameTaken { name: new_name })
+                        } else {
+                            if let Some(mut seq) = sequences.remove(&old_name) {
+                                seq.name = new_name.clone();
+                                history.record(Edit::Renamed {
+                                    old_name: old_name.clone(),
+                                    new_name: new_name.clone(),
+                                });
 
-                            if playing_sequences.contains(&old_name) {
-                                playing_sequences.retain(|name| name.clone() != old_name);
-                                playing_sequences.push(new_name.clone());
-                            }
+                                if playing_sequences.contains(&old_name) {
+                                    playing_sequences.retain(|name| name.clone() != old_name);
+                                    playing_sequences.push(new_name.clone());
+                                }
+
+                                if queued_sequences.contains(&old_name) {
+                                    queued_sequences.retain(|name| name.clone() != old_name);
+                                    queued_sequences.push(new_name.clone());
+                                }
+
+                                if queued_stop_sequences.contains(&old_name) {
+                                    queued_stop_sequences.retain(|name| name.clone() != old_name);
+                                    queued_stop_sequences.push(new_name.clone());
+                                }
+
+                                if let Some(step) = paused_sequences.remove(&old_name) {
+                                    paused_sequences.insert(new_name.clone(), step);
+                                }
+
+                                if let Some(offset) = step_offsets.remove(&old_name) {
+                                    step_offsets.insert(new_name.clone(), offset);
+                                }
+
+                                if let Some(pulse) = launch_pulses.remove(&old_name) {
+                                    launch_pulses.insert(new_name.clone(), pulse);
+                                }
+
+                                scheduled_messages
+                                    .iter_mut()
+                                    .filter(|(_, name, _)| *name == old_name)
+                                    .for_each(|(_, name, _)| *name = new_name.clone());
+
+                                scenes
+                                    .values_mut()
+                                    .flat_map(|scene| scene.sequences.iter_mut())
+                                    .chain(
+                                        arrangement
+                                            .sections
+                                            .iter_mut()
+                                            .flat_map(|section| section.sequences.iter_mut()),
+                                    )
+                                    .filter(|name| **name == old_name)
+                                    .for_each(|name| *name = new_name.clone());
+
+                                if let Some(recorder) = recorder.as_mut()
+                                    && recorder.sequence == old_name
+                                {
+                                    recorder.sequence = new_name.clone();
+                                }
 
-                            if queued_sequences.contains(&old_name) {
-                                queued_sequences.retain(|name| name.clone() != old_name);
-                                queued_sequences.push(new_name.clone());
+                                sequences.insert(new_name, seq);
                             }
 
-                            sequences.insert(new_name, seq);
+                            Ok(())
                         }
                     }
                     SequencerControlCmd::RmSequence { name } => {
@@ -367,39 +1022,124 @@ pub async fn sequencer_start(
                         queued_sequences.retain(|n| n != &name);
                         playing_sequences.retain(|n| n != &name);
                         queued_stop_sequences.retain(|stop_name| stop_name != &name);
-
-                        if sequences.is_empty()
-                            || (playing_sequences.is_empty() && queued_sequences.is_empty())
+                        paused_sequences.remove(&name);
+                        step_offsets.remove(&name);
+                        launch_pulses.remove(&name);
+                        recorder.take_if(|recorder| recorder.sequence == name);
+
+                        if (sequences.is_empty()
+                            || (playing_sequences.is_empty() && queued_sequences.is_empty()))
+                            && arrangement_pos.is_none()
                         {
                             counter = 0.;
                         }
-                    }
-                    SequencerControlCmd::Play(names) => names.into_iter().for_each(|name| {
-                        queued_stop_sequences.retain(|stop_name| stop_name != &name);
 
-                        if sequences.contains_key(&name) {
-                            queued_sequences.push(name.clone());
+                        Ok(())
+                    }
+                    SequencerControlCmd::Play(names, quant) => {
+                        names.into_iter().for_each(|name| {
+                            queued_stop_sequences.retain(|stop_name| stop_name != &name);
+
+                            if let Some(step) = paused_sequences.remove(&name) {
+                                let step_n = resume_sequence(
+                                    &sequences,
+                                    &mut playing_sequences,
+                                    &mut step_offsets,
+                                    name,
+                                    step,
+                                    counter,
+                                    unwrap_rw_lock(&bpq, 24.),
+                                    &mb_sender,
+                                    &conn,
+                                );
 
-                            let i = (counter / (unwrap_rw_lock(&bpq, 24.) / 4.)) as usize;
-                            let msg = MsgFromServer::SequenceWillStart {
-                                sequence_name: name.clone(),
-                                after_steps: 16 - (i % 16),
-                            };
+                                if let Some(step_n) = step_n
+                                    && !transport_running
+                                    && clock_source.is_internal()
+                                {
+                                    transport_running = true;
+                                    send_transport(
+                                        &transport,
+                                        Transport::Continue {
+                                            position: step_n.min(0x3FFF) as u16,
+                                        },
+                                    );
+                                }
+                            } else if let Some(sequence) = sequences.get(&name) {
+                                queue_start(
+                                    sequence,
+                                    quant.unwrap_or(launch_quant),
+                                    counter,
+                                    unwrap_rw_lock(&bpq, 24.),
+                                    &playing_sequences,
+                                    &mut queued_sequences,
+                                    &mut step_offsets,
+                                    &mut launch_pulses,
+                                    &mb_sender,
+                                    &conn,
+                                );
+                            }
+                        });
 
-                            send_msg(&mb_sender, &conn, msg);
-                        } else {
-                            error!("unknown sequence, \"{name}\"");
-                        }
-                    }),
+                        Ok(())
+                    }
                     SequencerControlCmd::PlayAll => {
-                        sequences.keys().for_each(|name| {
-                            let msg = MsgFromServer::SequenceStarted {
-                                sequence_name: name.clone(),
-                            };
+                        let beats = unwrap_rw_lock(&bpq, 24.);
+
+                        paused_sequences.drain().for_each(|(name, step)| {
+                            let step_n = resume_sequence(
+                                &sequences,
+                                &mut playing_sequences,
+                                &mut step_offsets,
+                                name,
+                                step,
+                                counter,
+                                beats,
+                                &mb_sender,
+                                &conn,
+                            );
 
-                            send_msg(&mb_sender, &conn, msg);
-                            queued_sequences.push(name.clone());
+                            if let Some(step_n) = step_n
+                                && !transport_running
+                                && clock_source.is_internal()
+                            {
+                                transport_running = true;
+                                send_transport(
+                                    &transport,
+                                    Transport::Continue {
+                                        position: step_n.min(0x3FFF) as u16,
+                                    },
+                                );
+                            }
+                        });
+
+                        queued_stop_sequences.clear();
+
+                        let starting: Vec<&Sequence> = sequences
+                            .iter()
+                            .filter(|(name, _)| {
+                                !playing_sequences.contains(name)
+                                    && !queued_sequences.contains(name)
+                            })
+                            .map(|(_, sequence)| sequence)
+                            .collect();
+
+                        starting.into_iter().for_each(|sequence| {
+                            queue_start(
+                                sequence,
+                                launch_quant,
+                                counter,
+                                beats,
+                                &playing_sequences,
+                                &mut queued_sequences,
+                                &mut step_offsets,
+                                &mut launch_pulses,
+                                &mb_sender,
+                                &conn,
+                            );
                         });
+
+                        Ok(())
                     }
                     SequencerControlCmd::Stop(names) => {
                         names.iter().for_each(|name| {
@@ -413,476 +1153,759 @@ pub async fn sequencer_start(
 
                         queued_sequences.retain(|name| !names.contains(name));
                         playing_sequences.retain(|name| !names.contains(name));
+                        paused_sequences.retain(|name, _| !names.contains(name));
+
+                        if recorder
+                            .as_ref()
+                            .is_some_and(|recorder| names.contains(&recorder.sequence))
+                        {
+                            let beats = unwrap_rw_lock(&bpq, 24.);
+                            let pulse = pulse_pos(
+                                counter,
+                                &scheduler,
+                                &clock_source,
+                                unwrap_rw_lock(&tempo, 99.),
+                                beats,
+                            );
+                            stop_recording(
+                                &mut recorder,
+                                &mut sequences,
+                                &step_offsets,
+                                pulse,
+                                beats,
+                                &mb_sender,
+                                &conn,
+                            );
+                        }
 
-                        if playing_sequences.is_empty() && queued_sequences.is_empty() {
+                        if playing_sequences.is_empty()
+                            && queued_sequences.is_empty()
+                            && arrangement_pos.is_none()
+                        {
                             counter = 0.;
                             let msg = MsgFromServer::SyncPulseReset();
 
                             send_msg(&mb_sender, &conn, msg);
+                            sync_reset_automations(&automation_cmds);
                         }
+
+                        Ok(())
                     }
                     SequencerControlCmd::StopAll => {
-                        playing_sequences.iter().for_each(|name| {
-                            let msg = MsgFromServer::SequenceStoped {
-                                sequence_name: name.clone(),
-                                step_n: (counter / (unwrap_rw_lock(&bpq, 24.) / 4.)) as usize,
-                            };
+                        playing_sequences
+                            .iter()
+                            .chain(paused_sequences.keys())
+                            .for_each(|name| {
+                                let msg = MsgFromServer::SequenceStoped {
+                                    sequence_name: name.clone(),
+                                    step_n: (counter / (unwrap_rw_lock(&bpq, 24.) / 4.)) as usize,
+                                };
 
-                            send_msg(&mb_sender, &conn, msg);
-                        });
+                                send_msg(&mb_sender, &conn, msg);
+                            });
+
+                        let beats = unwrap_rw_lock(&bpq, 24.);
+                        let pulse = pulse_pos(
+                            counter,
+                            &scheduler,
+                            &clock_source,
+                            unwrap_rw_lock(&tempo, 99.),
+                            beats,
+                        );
+                        stop_recording(
+                            &mut recorder,
+                            &mut sequences,
+                            &step_offsets,
+                            pulse,
+                            beats,
+                            &mb_sender,
+                            &conn,
+                        );
 
                         queued_sequences.clear();
                         playing_sequences.clear();
+                        paused_sequences.clear();
+                        pending_tempo = None;
                         counter = 0.;
 
+                        if arrangement_pos.take().is_some() {
+                            send_msg(&mb_sender, &conn, MsgFromServer::ArrangementEnded());
+                        }
+
                         let msg = MsgFromServer::SyncPulseReset();
 
                         send_msg(&mb_sender, &conn, msg);
+                        sync_reset_automations(&automation_cmds);
+
+                        Ok(())
                     }
-                    SequencerControlCmd::QueueStop(names) => {
-                        queued_stop_sequences.append(&mut names.clone());
+                    SequencerControlCmd::QueueStop(names, quant) => {
+                        let beats = unwrap_rw_lock(&bpq, 24.);
+                        let quant = quant.unwrap_or(launch_quant);
+
                         queued_sequences.retain(|name| !names.contains(name));
 
-                        playing_sequences.iter().for_each(|name| {
-                            if names.contains(name) {
-                                let i = (counter / (unwrap_rw_lock(&bpq, 24.) / 4.)) as usize;
-                                let msg = MsgFromServer::SequenceWillStop {
-                                    sequence_name: name.clone(),
-                                    after_steps: 16 - (i % 16),
-                                };
+                        playing_sequences
+                            .iter()
+                            .filter(|name| names.contains(name))
+                            .filter_map(|name| sequences.get(name))
+                            .for_each(|sequence| {
+                                queue_stop(
+                                    sequence,
+                                    quant,
+                                    counter,
+                                    beats,
+                                    &mut queued_stop_sequences,
+                                    &mut launch_pulses,
+                                    &mb_sender,
+                                    &conn,
+                                );
+                            });
 
-                                send_msg(&mb_sender, &conn, msg);
-                            }
+                        Ok(())
+                    }
+                    SequencerControlCmd::SetScene(scene) => {
+                        scenes.insert(scene.name.clone(), scene);
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::RmScene { name } => match scenes.remove(&name) {
+                        Some(_) => Ok(()),
+                        None => Err(SequencerError::UnknownScene { name }),
+                    },
+                    SequencerControlCmd::GetScenes { responder } => {
+                        let mut all_scenes: Vec<Scene> = scenes.values().cloned().collect();
+                        all_scenes.sort_by(|a, b| a.name.cmp(&b.name));
+
+                        if let Err(e) = responder.send(all_scenes) {
+                            error!("sending scenes failed with error: {e:?}");
+                        }
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::LaunchScene { name, quant } => {
+                        if let Some(scene) = scenes.get(&name) {
+                            let beats = unwrap_rw_lock(&bpq, 24.);
+                            let quant = quant.unwrap_or(launch_quant);
+                            let launch =
+                                launch_pulse(quant, counter, NoteDuration::default(), beats);
+
+                            launch_group(
+                                &scene.sequences,
+                                quant,
+                                counter,
+                                beats,
+                                &sequences,
+                                &playing_sequences,
+                                &mut queued_sequences,
+                                &mut queued_stop_sequences,
+                                &mut paused_sequences,
+                                &mut step_offsets,
+                                &mut launch_pulses,
+                                &mb_sender,
+                                &conn,
+                            );
+
+                            pending_tempo = scene.tempo.map(|tempo| (launch, tempo));
+
+                            let msg = MsgFromServer::SceneLaunched {
+                                scene_name: name,
+                                after_steps: launch_wait(launch, counter, beats).0,
+                            };
+
+                            send_msg(&mb_sender, &conn, msg);
+
+                            Ok(())
+                        } else {
+                            Err(SequencerError::UnknownScene { name })
+                        }
+                    }
+                    SequencerControlCmd::SetArrangement(new_arrangement) => {
+                        arrangement = new_arrangement;
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::GetArrangement { responder } => {
+                        if let Err(e) = responder.send(arrangement.clone()) {
+                            error!("sending arrangement failed with error: {e:?}");
+                        }
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::PlayArrangement { from } => {
+                        if let Some(section) = arrangement.sections.get(from) {
+                            let beats = unwrap_rw_lock(&bpq, 24.);
+                            let launch =
+                                launch_pulse(launch_quant, counter, NoteDuration::default(), beats);
+
+                            launch_group(
+                                &section.sequences,
+                                launch_quant,
+                                counter,
+                                beats,
+                                &sequences,
+                                &playing_sequences,
+                                &mut queued_sequences,
+                                &mut queued_stop_sequences,
+                                &mut paused_sequences,
+                                &mut step_offsets,
+                                &mut launch_pulses,
+                                &mb_sender,
+                                &conn,
+                            );
+
+                            pending_tempo = section.tempo.map(|tempo| (launch, tempo));
+                            arrangement_pos = Some(ArrangementPos {
+                                index: from,
+                                start: launch,
+                                started: false,
+                            });
+
+                            Ok(())
+                        } else {
+                            Err(SequencerError::SectionOutOfRange {
+                                index: from,
+                                len: arrangement.sections.len(),
+                            })
+                        }
+                    }
+                    SequencerControlCmd::StopArrangement => {
+                        if arrangement_pos.take().is_some() {
+                            send_msg(&mb_sender, &conn, MsgFromServer::ArrangementEnded());
+                        }
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::Pause(names) => {
+                        if let Some(name) =
+                            names.iter().find(|name| !playing_sequences.contains(name))
+                        {
+                            respond(
+                                responder,
+                                Err(SequencerError::NotPlaying { name: name.clone() }),
+                            );
+                            continue;
+                        }
+
+                        let beats = unwrap_rw_lock(&bpq, 24.);
+
+                        names.into_iter().for_each(|name| {
+                            pause_sequence(
+                                &sequences,
+                                &mut playing_sequences,
+                                &mut paused_sequences,
+                                &step_offsets,
+                                name,
+                                counter,
+                                beats,
+                                &mb_sender,
+                                &conn,
+                            );
+                        });
+                        queued_stop_sequences
+                            .retain(|stop_name| playing_sequences.contains(stop_name));
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::PauseAll => {
+                        let beats = unwrap_rw_lock(&bpq, 24.);
+
+                        playing_sequences.clone().into_iter().for_each(|name| {
+                            pause_sequence(
+                                &sequences,
+                                &mut playing_sequences,
+                                &mut paused_sequences,
+                                &step_offsets,
+                                name,
+                                counter,
+                                beats,
+                                &mb_sender,
+                                &conn,
+                            );
                         });
+                        queued_stop_sequences.clear();
+
+                        Ok(())
                     }
-                    SequencerControlCmd::Pause(_names) => warn!("not implemented yet"),
-                    SequencerControlCmd::PauseAll => warn!("not implemented yet"),
                     SequencerControlCmd::AddNote {
                         sequence,
                         step: step_i,
                         note,
                         velocity,
                         note_len,
-                    } => {
-                        if let Some(seq) = sequences.get_mut(&sequence) {
-                            if let Some(step) = seq.steps.get_mut(step_i) {
-                                step.push(MidiMsg::PlayNote {
-                                    note,
-                                    velocity,
-                                    duration: note_len.unwrap_or(NoteDuration::Sn(1)),
-                                });
-                            } else {
-                                error!(
-                                    "invalid step, {step_i}. sequence, \"{sequence}\", only has {}, steps",
-                                    seq.steps.len()
-                                );
-                            }
-                        } else {
-                            error!("sequence not found");
-                        }
-                    }
+                    } => get_sequence(&mut sequences, &sequence)
+                        .and_then(|seq| get_step(seq, step_i))
+                        .map(|step| {
+                            step.push(MidiMsg::PlayNote {
+                                note,
+                                velocity,
+                                duration: note_len.unwrap_or(NoteDuration::Sn(1)),
+                            })
+                        }),
                     SequencerControlCmd::RmNote {
                         sequence,
                         step: step_i,
                         note,
-                    } => {
-                        if let Some(seq) = sequences.get_mut(&sequence) {
-                            if let Some(step) = seq.steps.get_mut(step_i) {
-                                step.retain(|msg| {
-                                    let MidiMsg::PlayNote {
-                                        note: msg_note,
-                                        velocity: _,
-                                        duration: _,
-                                    } = msg
-                                    else {
-                                        return true;
-                                    };
-
-                                    *msg_note != note
-                                });
-                            } else {
-                                error!(
-                                    "invalid step, {step_i}. sequence, \"{sequence}\", only has {}, steps",
-                                    seq.steps.len()
-                                );
-                            }
-                        } else {
-                            error!("sequence not found");
-                        }
-                    }
+                    } => get_sequence(&mut sequences, &sequence)
+                        .and_then(|seq| get_step(seq, step_i))
+                        .map(|step| {
+                            step.retain(|msg| {
+                                let MidiMsg::PlayNote {
+                                    note: msg_note,
+                                    velocity: _,
+                                    duration: _,
+                                } = msg
+                                else {
+                                    return true;
+                                };
+
+                                *msg_note != note
+                            })
+                        }),
                     SequencerControlCmd::AddCmd {
-                        sequence: _,
-                        step: _,
-                        cmd: _,
-                    } => warn!("not implemented yet"),
+                        sequence,
+                        step: step_i,
+                        cmd,
+                    } => get_sequence(&mut sequences, &sequence)
+                        .and_then(|seq| get_step(seq, step_i))
+                        .map(|step| {
+                            step.retain(|msg| !msg.same_kind(&cmd));
+                            step.push(cmd);
+                        }),
                     SequencerControlCmd::RmCmd {
-                        sequence: _,
-                        step: _,
-                        cmd: _,
-                    } => warn!("not implemented yet"),
+                        sequence,
+                        step: step_i,
+                        cmd,
+                    } => get_sequence(&mut sequences, &sequence)
+                        .and_then(|seq| get_step(seq, step_i))
+                        .map(|step| step.retain(|msg| !msg.same_kind(&cmd))),
                     SequencerControlCmd::ChangeLenBy { sequence, amt } => {
-                        if let Some(seq) = sequences.get_mut(&sequence) {
-                            if amt > 0 {
-                                (0..amt).for_each(|_| seq.steps.push(Vec::default()));
-                            } else if amt < 0 && (seq.steps.len() + amt as usize) > 0 {
-                                (0..amt.abs()).for_each(|_| {
-                                    seq.steps.pop();
+                        get_sequence(&mut sequences, &sequence).and_then(|seq| {
+                            let len = seq.steps.len() as isize + amt;
+
+                            if len <= 0 {
+                                return Err(SequencerError::InvalidLen {
+                                    sequence: sequence.clone(),
+                                    len,
                                 });
                             }
-                        } else {
-                            error!("sequence not found");
-                        }
+
+                            seq.steps.resize_with(len as usize, Vec::default);
+
+                            Ok(())
+                        })
+                    }
+                    SequencerControlCmd::SetStepLen { sequence, step_len } => {
+                        get_sequence(&mut sequences, &sequence).map(|seq| seq.step_len = step_len)
+                    }
+                    SequencerControlCmd::SetSwing { sequence, swing } => {
+                        get_sequence(&mut sequences, &sequence)
+                            .map(|seq| seq.swing = swing.min(100))
+                    }
+                    SequencerControlCmd::SetGroove { sequence, groove } => {
+                        get_sequence(&mut sequences, &sequence).map(|seq| seq.groove = groove)
                     }
                     SequencerControlCmd::SaveSequence { sequence } => {
-                        if let Some(seq) = sequences.get(&sequence) {
-                            save_sequence(seq, &sequence).await;
-                            // logging handled in above function
-                        } else {
-                            error!("sequence not found");
+                        match get_sequence(&mut sequences, &sequence) {
+                            Ok(seq) => save_sequence(seq).await,
+                            Err(e) => Err(e),
                         }
                     }
                     SequencerControlCmd::ListSavedSequences { responder } => {
-                        let xdg_dirs = BaseDirectories::new();
-
-                        if let Some(mut data_dir) = xdg_dirs.data_home {
-                            data_dir.push("midi-daw");
-
-                            if let Ok(mut dir_contents) = read_dir(data_dir).await {
-                                let mut contents = Vec::default();
-
-                                while let Some(f_name) = dir_contents.next().await {
-                                    if let Ok(fname) = f_name {
-                                        if let Ok(ftype) = fname.file_type().await
-                                            && ftype.is_file()
-                                        {
-                                            contents.push(
-                                                fname.file_name().to_string_lossy().to_string(),
-                                            )
-                                        }
-                                    }
-                                }
+                        let saved = list_saved(None).await;
 
-                                if let Err(e) = responder.send(contents.clone()) {
-                                    error!(
-                                        "attempts to respond to front end failed with error: {e:?}"
-                                    );
-                                } else {
-                                    info!("listed sequences {:?}", contents);
-                                }
-                            } else {
-                                error!("failed to read saved files in data directory.");
-                            }
-                        } else {
-                            error!(
-                                "the '$HOME' env var could not be found. so no xdg dir could be set"
-                            );
+                        if let Ok(saved) = saved.as_ref() {
+                            info!("listed sequences {:?}", saved);
                         }
+
+                        if let Err(e) = responder.send(saved) {
+                            error!("attempts to respond to front end failed with error: {e:?}");
+                        }
+
+                        Ok(())
                     }
                     SequencerControlCmd::LoadSequence { sequence } => {
-                        // get file path
-                        let xdg_dirs = BaseDirectories::new();
-
-                        if let Some(mut data_dir) = xdg_dirs.data_home {
-                            data_dir.push("midi-daw");
-                            data_dir.push(format!("{}.json", sequence));
-
-                            // read file from disk
-                            if let Ok(file) = File::open(&data_dir).await {
-                                let mut reader = BufReader::new(file);
-
-                                let mut json_text = String::new();
-                                if reader.read_to_string(&mut json_text).await.is_ok() {
-                                    // let mut last_f_len = json_text.len();
-                                    // reader.read_line(&mut json_text);
-                                    // let mut f_len = json_text.len();
-                                    //
-                                    // while last_f_len != f_len {
-                                    //     last_f_len = f_len;
-                                    //     reader.read_line(&mut json_text);
-                                    //     f_len = json_text.len();
-                                    // }
-
-                                    // parse JSON
-                                    if let Ok(json) = serde_json::from_str::<Sequence>(&json_text) {
-                                        sequences.insert(json.name.clone(), json.clone());
-                                        info!("restored sequnce, '{}', from disk", json.name);
-                                    } else {
-                                        error!("parsing stored json failed.");
-                                    }
-                                } else {
-                                    error!("reading file failed.");
-                                }
-                            } else {
-                                error!(
-                                    "failed to open file. does, '{}', exist?",
-                                    data_dir.to_string_lossy()
-                                );
-                            }
-                        } else {
-                            error!(
-                                "the '$HOME' env var could not be found. so no xdg dir could be set"
-                            );
-                        }
+                        load_sequence(&sequence).await.map(|seq| {
+                            info!("restored sequnce, '{}', from disk", seq.name);
+                            let name = seq.name.clone();
+                            let before = sequences.insert(name.clone(), seq.clone());
+                            history.record_change(name, before, Some(seq));
+                        })
                     }
-                    SequencerControlCmd::RmSavedSequence { sequence } => {
-                        // get file path
-                        let xdg_dirs = BaseDirectories::new();
+                    SequencerControlCmd::RmSavedSequence { sequence } => rm_saved(None, &sequence)
+                        .await
+                        .inspect(|_| info!("sequence file removed")),
+                    SequencerControlCmd::SaveProject { project_name } => {
+                        let mut project_scenes: Vec<Scene> = scenes.values().cloned().collect();
+                        project_scenes.sort_by(|a, b| a.name.cmp(&b.name));
+
+                        let mut project_seqs: Vec<Sequence> = sequences.values().cloned().collect();
+                        project_seqs.sort_by(|a, b| a.name.cmp(&b.name));
+                        let mut project_devs: Vec<String> = virtual_devs
+                            .read()
+                            .map(|devs| devs.iter().cloned().collect())
+                            .unwrap_or_default();
+                        project_devs.sort();
+
+                        let project = Project {
+                            tempo: Some(unwrap_rw_lock(&tempo, 99.)),
+                            bpq: Some(unwrap_rw_lock(&bpq, 24.)),
+                            virtual_devs: project_devs,
+                            sequences: project_seqs,
+                            // queued sequences are as good as playing.
+                            playing: playing_sequences
+                                .iter()
+                                .chain(queued_sequences.iter())
+                                .cloned()
+                                .collect(),
+                            routes: routes
+                                .read()
+                                .map(|routes| routes.clone())
+                                .unwrap_or_default(),
+                            scenes: project_scenes,
+                            arrangement: arrangement.clone(),
+                            automations: list_automations(&automation_cmds).await,
+                            ..Project::new()
+                        };
 
-                        if let Some(mut data_dir) = xdg_dirs.data_home {
-                            data_dir.push("midi-daw");
+                        save_project(&project_name, &project).await
+                    }
+                    SequencerControlCmd::ListSavedProjects { responder } => {
+                        let saved = list_saved(Some(PROJECTS_DIR)).await;
 
-                            if !sequence.ends_with(".json") {
-                                data_dir.push(format!("{}.json", sequence));
-                            } else {
-                                data_dir.push(sequence);
-                            }
+                        if let Ok(saved) = saved.as_ref() {
+                            info!("listed projects {:?}", saved);
+                        }
 
-                            if data_dir.exists() {
-                                if let Err(e) = remove_file(&data_dir).await {
-                                    error!(
-                                        "removing, '{}', failed with error, {e}",
-                                        data_dir.to_string_lossy()
-                                    );
-                                } else {
-                                    info!("sequence file removed");
-                                }
-                            } else {
-                                warn!(
-                                    "the sequence file path: '{}' doesn't exist, can't remove",
-                                    data_dir.to_string_lossy()
-                                );
-                            }
-                        } else {
-                            error!(
-                                "the '$HOME' env var could not be found. so no xdg dir could be set"
-                            );
+                        if let Err(e) = responder.send(saved) {
+                            error!("attempts to respond to front end failed with error: {e:?}");
                         }
+
+                        Ok(())
                     }
-                    SequencerControlCmd::SaveProject { project_name } => {
-                        match serde_json::to_string(&sequences) {
-                            Ok(json) => {
-                                let xdg_dirs = BaseDirectories::new();
-
-                                if let Some(mut data_dir) = xdg_dirs.data_home {
-                                    data_dir.push("midi-daw");
-                                    data_dir.push("projects");
-
-                                    if !data_dir.exists() {
-                                        if let Err(e) = create_dir_all(&data_dir).await {
-                                            error!("creating data dir failed with error, {e}");
-                                        }
-                                    }
-
-                                    data_dir.push(format!("{}.json", project_name));
-                                    match File::create(&data_dir).await {
-                                        Ok(mut file) => match file.write_all(json.as_bytes()).await
-                                        {
-                                            Ok(_) => info!(
-                                                "saved '{project_name}' to file {}.json.",
-                                                project_name
-                                            ),
-                                            Err(e) => {
-                                                error!(
-                                                    "writing sequence data from project to file, {}, failed with an error, {e}",
-                                                    data_dir
-                                                        .file_name()
-                                                        .map(|name| name
-                                                            .to_string_lossy()
-                                                            .to_string())
-                                                        .unwrap_or(format!(
-                                                            "{}.json",
-                                                            project_name
-                                                        ))
-                                                )
-                                            }
-                                        },
-                                        Err(e) => {
-                                            error!(
-                                                "creating file to save sequence to failed with an error, {e}"
-                                            )
-                                        }
-                                    }
-                                } else {
-                                    error!(
-                                        "the '$HOME' env var could not be found. so no xdg dir could be set"
-                                    );
-                                }
-                            }
+                    SequencerControlCmd::LoadSavedProject { project_name } => {
+                        let project = match load_project(&project_name).await {
+                            Ok(project) => project,
                             Err(e) => {
-                                error!("attept to json-ify, failed with error, {e}");
+                                respond(responder, Err(e));
+                                continue;
                             }
+                        };
+
+                        // the project replaces what is open, so stop everything before swapping it in.
+                        playing_sequences
+                            .iter()
+                            .chain(paused_sequences.keys())
+                            .for_each(|name| {
+                                let msg = MsgFromServer::SequenceStoped {
+                                    sequence_name: name.clone(),
+                                    step_n: (counter / (unwrap_rw_lock(&bpq, 24.) / 4.)) as usize,
+                                };
+
+                                send_msg(&mb_sender, &conn, msg);
+                            });
+
+                        let beats = unwrap_rw_lock(&bpq, 24.);
+                        let pulse = pulse_pos(
+                            counter,
+                            &scheduler,
+                            &clock_source,
+                            unwrap_rw_lock(&tempo, 99.),
+                            beats,
+                        );
+                        stop_recording(
+                            &mut recorder,
+                            &mut sequences,
+                            &step_offsets,
+                            pulse,
+                            beats,
+                            &mb_sender,
+                            &conn,
+                        );
+
+                        queued_sequences.clear();
+                        queued_stop_sequences.clear();
+                        playing_sequences.clear();
+                        paused_sequences.clear();
+                        step_offsets.clear();
+                        launch_pulses.clear();
+                        pending_tempo = None;
+                        counter = 0.;
+
+                        if arrangement_pos.take().is_some() {
+                            send_msg(&mb_sender, &conn, MsgFromServer::ArrangementEnded());
                         }
-                    }
-                    SequencerControlCmd::ListSavedProjects { responder } => {
-                        let xdg_dirs = BaseDirectories::new();
-
-                        if let Some(mut data_dir) = xdg_dirs.data_home {
-                            data_dir.push("midi-daw");
-                            data_dir.push("projects");
-
-                            if let Ok(mut dir_contents) = read_dir(data_dir).await {
-                                let mut contents = Vec::default();
-
-                                while let Some(f_name) = dir_contents.next().await {
-                                    if let Ok(fname) = f_name {
-                                        if let Ok(ftype) = fname.file_type().await
-                                            && ftype.is_file()
-                                        {
-                                            contents.push(
-                                                fname.file_name().to_string_lossy().to_string(),
-                                            )
-                                        }
-                                    }
-                                }
 
-                                if let Err(e) = responder.send(contents.clone()) {
-                                    error!(
-                                        "attempts to respond to front end failed with error: {e:?}"
-                                    );
-                                } else {
-                                    info!("listed projects {:?}", contents);
-                                }
-                            } else {
-                                error!("failed to read saved files in data directory.");
-                            }
-                        } else {
-                            error!(
-                                "the '$HOME' env var could not be found. so no xdg dir could be set"
-                            );
+                        send_msg(&mb_sender, &conn, MsgFromServer::SyncPulseReset());
+
+                        if let Err(e) = automation_cmds.send(AutomationCmd::StopAll) {
+                            error!("stopping automations failed with error: {e}");
                         }
-                    }
-                    SequencerControlCmd::LoadSavedProject { project_name } => {
-                        // get file path
-                        let xdg_dirs = BaseDirectories::new();
-
-                        if let Some(mut data_dir) = xdg_dirs.data_home {
-                            data_dir.push("midi-daw");
-                            data_dir.push("projects");
-                            data_dir.push(format!("{}.json", project_name));
-
-                            // read file from disk
-                            if let Ok(file) = File::open(&data_dir).await {
-                                let mut reader = BufReader::new(file);
-
-                                let mut json_text = String::new();
-                                // let mut last_f_len = json_text.len();
-                                if reader.read_to_string(&mut json_text).await.is_ok() {
-                                    // let mut f_len = json_text.len();
-                                    //
-                                    // while last_f_len != f_len {
-                                    //     last_f_len = f_len;
-                                    //     reader.read_line(&mut json_text);
-                                    //     f_len = json_text.len();
-                                    // }
-
-                                    // parse JSON
-                                    if let Ok(json) =
-                                        serde_json::from_str::<AllSequences>(&json_text)
-                                    {
-                                        sequences.extend(json.clone().into_iter());
-                                        info!("restored project, '{}', from disk", project_name);
-                                    } else {
-                                        warn!("{json_text}");
-                                        error!("parsing stored json failed.");
-                                    }
-                                } else {
-                                    error!("failed to read file");
-                                }
-                            } else {
-                                error!(
-                                    "failed to open file. does, '{}', exist?",
-                                    data_dir.to_string_lossy()
+
+                        restore_setup(
+                            &project,
+                            &tempo,
+                            &bpq,
+                            &virtual_devs,
+                            &new_dev_tx,
+                            &automation_cmds,
+                        );
+
+                        // sequences the project doesn't have are removed, in the same undo step.
+                        let old_sequences = std::mem::take(&mut sequences);
+                        let mut changes = insert_sequences(&mut sequences, project.sequences);
+                        changes.iter_mut().for_each(|(name, before, _)| {
+                            *before = old_sequences.get(name).cloned()
+                        });
+                        changes.extend(
+                            old_sequences
+                                .into_iter()
+                                .filter(|(name, _)| !sequences.contains_key(name))
+                                .map(|(name, sequence)| (name, Some(sequence), None)),
+                        );
+                        history.record_changes(changes);
+                        scenes = project
+                            .scenes
+                            .into_iter()
+                            .map(|scene| (scene.name.clone(), scene))
+                            .collect();
+                        arrangement = project.arrangement;
+
+                        if let Ok(mut routes) = routes.write() {
+                            *routes = project.routes;
+                        }
+
+                        for name in project.playing {
+                            let Some(sequence) = sequences.get(&name) else {
+                                continue;
+                            };
+
+                            if !playing_sequences.contains(&name)
+                                && !queued_sequences.contains(&name)
+                            {
+                                queue_start(
+                                    sequence,
+                                    launch_quant,
+                                    counter,
+                                    unwrap_rw_lock(&bpq, 24.),
+                                    &playing_sequences,
+                                    &mut queued_sequences,
+                                    &mut step_offsets,
+                                    &mut launch_pulses,
+                                    &mb_sender,
+                                    &conn,
                                 );
                             }
-                        } else {
-                            error!(
-                                "the '$HOME' env var could not be found. so no xdg dir could be set"
-                            );
                         }
+
+                        info!("restored project, '{}', from disk", project_name);
+
+                        Ok(())
                     }
-                    SequencerControlCmd::RmSavedProject { project_name } => {
-                        // get file path
-                        let xdg_dirs = BaseDirectories::new();
-
-                        if let Some(mut data_dir) = xdg_dirs.data_home {
-                            data_dir.push("midi-daw");
-                            data_dir.push("projects");
-                            // data_dir.push(format!("{}.json", project_name));
-
-                            if !project_name.ends_with(".json") {
-                                data_dir.push(format!("{}.json", project_name));
-                            } else {
-                                data_dir.push(project_name);
-                            }
+                    SequencerControlCmd::RestoreSequence(seq) => {
+                        sequences.insert(seq.name.clone(), seq);
 
-                            if data_dir.exists() {
-                                if let Err(e) = remove_file(&data_dir).await {
-                                    error!(
-                                        "removing, '{}', failed with error, {e}",
-                                        data_dir.to_string_lossy()
-                                    );
-                                } else {
-                                    info!("sequence file removed");
-                                }
-                            } else {
-                                warn!(
-                                    "the project file path: '{}' doesn't exist, can't remove",
-                                    data_dir.to_string_lossy()
-                                );
-                            }
-                        } else {
-                            error!(
-                                "the '$HOME' env var could not be found. so no xdg dir could be set"
-                            );
+                        Ok(())
+                    }
+                    SequencerControlCmd::Undo => match history.undo() {
+                        Some(edit) => {
+                            let msg = MsgFromServer::Undone {
+                                sequence_names: edit.sequence_names(),
+                            };
+
+                            history_cmds.extend(edit.into_cmds());
+                            send_msg(&mb_sender, &conn, msg);
+
+                            Ok(())
+                        }
+                        None => Err(SequencerError::NothingToUndo),
+                    },
+                    SequencerControlCmd::Redo => match history.redo() {
+                        Some(edit) => {
+                            let msg = MsgFromServer::Redone {
+                                sequence_names: edit.sequence_names(),
+                            };
+
+                            history_cmds.extend(edit.into_cmds());
+                            send_msg(&mb_sender, &conn, msg);
+
+                            Ok(())
                         }
+                        None => Err(SequencerError::NothingToRedo),
+                    },
+                    SequencerControlCmd::ImportSequences(imported) => {
+                        imported
+                            .iter()
+                            .for_each(|seq| info!("imported sequence, \"{}\"", seq.name));
+                        let changes = insert_sequences(&mut sequences, imported);
+                        history.record_changes(changes);
+
+                        Ok(())
                     }
+                    SequencerControlCmd::ExportSmf {
+                        sequence,
+                        responder,
+                    } => {
+                        let smf = match sequences.get(&sequence) {
+                            Some(seq) => to_smf(&sequence, &[seq], unwrap_rw_lock(&tempo, 99.))
+                                .map_err(SequencerError::from),
+                            None => Err(SequencerError::UnknownSequence { name: sequence }),
+                        };
+
+                        if let Err(e) = responder.send(smf) {
+                            error!("sending midi file failed with error: {e:?}");
+                        }
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::ExportProjectSmf {
+                        project_name,
+                        responder,
+                    } => {
+                        let mut seqs: Vec<&Sequence> = sequences.values().collect();
+                        seqs.sort_by(|a, b| a.name.cmp(&b.name));
+
+                        let smf = to_smf(&project_name, &seqs, unwrap_rw_lock(&tempo, 99.))
+                            .map_err(SequencerError::from);
+
+                        if let Err(e) = responder.send(smf) {
+                            error!("sending midi file failed with error: {e:?}");
+                        }
+
+                        Ok(())
+                    }
+                    SequencerControlCmd::RmSavedProject { project_name } => {
+                        rm_saved(Some(PROJECTS_DIR), &project_name)
+                            .await
+                            .inspect(|_| info!("project file removed"))
+                    }
+                };
+
+                if let Some(name) = edited {
+                    let after = sequences.get(&name).cloned();
+
+                    history.record_change(name, before, after);
+                }
+
+                // an undo or redo that can't be made anymore, (eg: renaming onto a name that's since
+                // been taken), stays where it was.
+                if from_history && res.is_err() {
+                    history_cmds.clear();
+                    history.replay_failed();
                 }
+
+                respond(responder, res);
             }
         }
     }
 }
 
-async fn save_sequence(seq: &Sequence, sequence_name: &str) {
-    match serde_json::to_string(seq) {
-        Ok(json) => {
-            let xdg_dirs = BaseDirectories::new();
+/// logs the result of a control command & sends it to whatever's waiting on it.
+fn respond(responder: Option<CmdResponder>, res: Result<(), SequencerError>) {
+    if let Err(e) = res.as_ref() {
+        error!("{e}");
+    }
 
-            if let Some(mut data_dir) = xdg_dirs.data_home {
-                data_dir.push("midi-daw");
+    if let Some(responder) = responder
+        && responder.send(res).is_err()
+    {
+        warn!("the sender of a control command stopped waiting for a response");
+    }
+}
 
-                if !data_dir.exists() {
-                    if let Err(e) = create_dir_all(&data_dir).await {
-                        error!("creating data dir failed with error, {e}");
-                    }
-                }
+/// the automations running on the server, to save with a project.
+async fn list_automations(automation_cmds: &Sender<AutomationCmd>) -> Vec<StartAutomationBody> {
+    let (responder, recv_er) = oneshot::channel();
 
-                data_dir.push(format!("{}.json", seq.name));
-
-                match File::create(&data_dir).await {
-                    Ok(mut file) => match file.write_all(json.as_bytes()).await {
-                        Ok(_) => info!("saved '{sequence_name}' to file {}.", seq.name),
-                        Err(e) => {
-                            error!(
-                                "writing sequence data from sequence, {}, to file, {}, failed with an error, {e}",
-                                seq.name,
-                                data_dir
-                                    .file_name()
-                                    .map(|name| name.to_string_lossy().to_string())
-                                    .unwrap_or(format!("{}.json", seq.name))
-                            )
-                        }
-                    },
-                    Err(e) => {
-                        error!("creating file to save sequence to failed with an error, {e}")
-                    }
-                }
-            } else {
-                error!("the '$HOME' env var could not be found. so no xdg dir could be set");
+    if let Err(e) = automation_cmds.send(AutomationCmd::List { responder }) {
+        error!("listing automations failed with error: {e}");
+        return Vec::default();
+    }
+
+    recv_er.await.unwrap_or_else(|e| {
+        error!("reading the running automations failed with error: {e}");
+        Vec::default()
+    })
+}
+
+/// restores a project's tempo, BPQ, virtual devices & automations.
+fn restore_setup(
+    project: &Project,
+    tempo: &Tempo,
+    bpq: &BPQ,
+    virtual_devs: &VirtualDevs,
+    new_dev_tx: &Sender<MidiDev>,
+    automation_cmds: &Sender<AutomationCmd>,
+) {
+    if let Some(project_tempo) = project.tempo
+        && let Ok(mut tempo) = tempo.write()
+    {
+        *tempo = project_tempo;
+    }
+
+    if let Some(project_bpq) = project.bpq
+        && let Ok(mut bpq) = bpq.write()
+    {
+        *bpq = project_bpq;
+    }
+
+    if let Ok(mut virtual_devs) = virtual_devs.write() {
+        for dev in project.virtual_devs.iter() {
+            if virtual_devs.insert(dev.clone())
+                && let Err(e) = new_dev_tx.send(MidiDev::CreateVirtual(dev.clone()))
+            {
+                error!("making virtual device, \"{dev}\", failed with error: {e}");
             }
         }
-        Err(e) => {
-            error!("attept to json-ify, failed with error, {e}");
+    }
+
+    project.automations.iter().for_each(|conf| {
+        let Ok(automation) = make_automation(conf) else {
+            return;
+        };
+        let msg = AutomationCmd::Start {
+            conf: conf.clone(),
+            automation,
+        };
+
+        if let Err(e) = automation_cmds.send(msg) {
+            error!(
+                "starting automation, \"{}\", failed with error: {e}",
+                conf.name
+            );
         }
+    });
+}
+
+/// the sequence called name.
+fn get_sequence<'a>(
+    sequences: &'a mut AllSequences,
+    name: &SequenceName,
+) -> Result<&'a mut Sequence, SequencerError> {
+    sequences
+        .get_mut(name)
+        .ok_or_else(|| SequencerError::UnknownSequence { name: name.clone() })
+}
+
+/// step_i of sequence.
+fn get_step(sequence: &mut Sequence, step_i: usize) -> Result<&mut Step, SequencerError> {
+    let len = sequence.steps.len();
+
+    sequence
+        .steps
+        .get_mut(step_i)
+        .ok_or_else(|| SequencerError::StepOutOfRange {
+            sequence: sequence.name.clone(),
+            step: step_i,
+            len,
+        })
+}
+
+/// errors if any of names isn't a sequence.
+fn check_sequences(sequences: &AllSequences, names: &[SequenceName]) -> Result<(), SequencerError> {
+    match names.iter().find(|name| !sequences.contains_key(*name)) {
+        Some(name) => Err(SequencerError::UnknownSequence { name: name.clone() }),
+        None => Ok(()),
     }
 }
 
@@ -893,3 +1916,359 @@ fn send_msg(mb_sender: &MbServerHandle, conn: &Uuid, msg_struct: MsgFromServer)
         error!("failed to send msg to msg bus");
     }
 }
+
+/// the number of pulses in one step of length step_len.
+fn step_pulses(step_len: NoteDuration, bpq: f64) -> f64 {
+    step_len.quarter_notes() * bpq
+}
+
+/// the number of periods, (each pulses long), that started before the pulse counter. so the
+/// next one starts on or after counter. periods that don't start on a pulse happen on the pulse
+/// after they start.
+fn periods_before(counter: f64, pulses: f64) -> usize {
+    (((counter - 1. + STEP_EPSILON) / pulses).floor() + 1.).max(0.) as usize
+}
+
+/// the first step, (counted from when the counter was last reset), of a sequence stepping in
+/// step_len's that hasn't been played before the pulse counter.
+fn next_seq_step(counter: f64, step_len: NoteDuration, bpq: f64) -> usize {
+    periods_before(counter, step_pulses(step_len, bpq))
+}
+
+/// the pulse a sequence, (stepping in step_len's), queued at the pulse counter starts or stops on.
+fn launch_pulse(quant: LaunchQuant, counter: f64, step_len: NoteDuration, bpq: f64) -> f64 {
+    let quantum = match quant {
+        LaunchQuant::Immediate() => return counter,
+        LaunchQuant::Step() => step_pulses(step_len, bpq),
+        LaunchQuant::Beat() => bpq,
+        LaunchQuant::Bars(n) => bpq * 4. * n.max(1) as f64,
+    };
+
+    periods_before(counter, quantum) as f64 * quantum
+}
+
+/// how long until the launch pulse, in sixteenth note steps, (rounded up), & pulses.
+fn launch_wait(launch: f64, counter: f64, bpq: f64) -> (usize, usize) {
+    let pulses = (launch - counter - STEP_EPSILON).ceil().max(0.);
+
+    (
+        (pulses / (bpq / 4.) - STEP_EPSILON).ceil().max(0.) as usize,
+        pulses as usize,
+    )
+}
+
+/// queues a sequence to start on its launch pulse, unless it's already playing.
+#[allow(clippy::too_many_arguments)]
+fn queue_start(
+    sequence: &Sequence,
+    quant: LaunchQuant,
+    counter: f64,
+    bpq: f64,
+    playing_sequences: &[SequenceName],
+    queued_sequences: &mut Vec<SequenceName>,
+    step_offsets: &mut FxHashMap<SequenceName, usize>,
+    launch_pulses: &mut FxHashMap<SequenceName, f64>,
+    mb_sender: &MbServerHandle,
+    conn: &Uuid,
+) {
+    let name = &sequence.name;
+
+    if playing_sequences.contains(name) {
+        return;
+    }
+
+    let launch = launch_pulse(quant, counter, sequence.step_len, bpq);
+    let (after_steps, after_pulses) = launch_wait(launch, counter, bpq);
+
+    step_offsets.remove(name);
+    launch_pulses.insert(name.clone(), launch);
+
+    if !queued_sequences.contains(name) {
+        queued_sequences.push(name.clone());
+    }
+
+    let msg = MsgFromServer::SequenceWillStart {
+        sequence_name: name.clone(),
+        after_steps,
+        after_pulses,
+    };
+
+    send_msg(mb_sender, conn, msg);
+}
+
+/// queues a playing sequence to stop on its launch pulse.
+#[allow(clippy::too_many_arguments)]
+fn queue_stop(
+    sequence: &Sequence,
+    quant: LaunchQuant,
+    counter: f64,
+    bpq: f64,
+    queued_stop_sequences: &mut Vec<SequenceName>,
+    launch_pulses: &mut FxHashMap<SequenceName, f64>,
+    mb_sender: &MbServerHandle,
+    conn: &Uuid,
+) {
+    let name = &sequence.name;
+    let launch = launch_pulse(quant, counter, sequence.step_len, bpq);
+    let (after_steps, after_pulses) = launch_wait(launch, counter, bpq);
+
+    launch_pulses.insert(name.clone(), launch);
+
+    if !queued_stop_sequences.contains(name) {
+        queued_stop_sequences.push(name.clone());
+    }
+
+    let msg = MsgFromServer::SequenceWillStop {
+        sequence_name: name.clone(),
+        after_steps,
+        after_pulses,
+    };
+
+    send_msg(mb_sender, conn, msg);
+}
+
+/// queues the sequences in names to start & every other sequence to stop, (ie: for a scene).
+#[allow(clippy::too_many_arguments)]
+fn launch_group(
+    names: &[SequenceName],
+    quant: LaunchQuant,
+    counter: f64,
+    bpq: f64,
+    sequences: &AllSequences,
+    playing_sequences: &[SequenceName],
+    queued_sequences: &mut Vec<SequenceName>,
+    queued_stop_sequences: &mut Vec<SequenceName>,
+    paused_sequences: &mut FxHashMap<SequenceName, usize>,
+    step_offsets: &mut FxHashMap<SequenceName, usize>,
+    launch_pulses: &mut FxHashMap<SequenceName, f64>,
+    mb_sender: &MbServerHandle,
+    conn: &Uuid,
+) {
+    // stop everything that isn't in the group.
+    queued_sequences.retain(|name| names.contains(name));
+    playing_sequences
+        .iter()
+        .filter(|name| !names.contains(name))
+        .filter_map(|name| sequences.get(name))
+        .for_each(|sequence| {
+            queue_stop(
+                sequence,
+                quant,
+                counter,
+                bpq,
+                queued_stop_sequences,
+                launch_pulses,
+                mb_sender,
+                conn,
+            );
+        });
+
+    // start the group's sequences that aren't already playing.
+    names.iter().for_each(|name| {
+        queued_stop_sequences.retain(|stop_name| stop_name != name);
+
+        if playing_sequences.contains(name) || queued_sequences.contains(name) {
+            return;
+        }
+
+        if let Some(sequence) = sequences.get(name) {
+            paused_sequences.remove(name);
+            queue_start(
+                sequence,
+                quant,
+                counter,
+                bpq,
+                playing_sequences,
+                queued_sequences,
+                step_offsets,
+                launch_pulses,
+                mb_sender,
+                conn,
+            );
+        } else {
+            warn!("unknown sequence, \"{name}\"");
+        }
+    });
+}
+
+/// true if a queued sequence should start or stop on the pulse counter.
+fn launch_due(
+    launch_pulses: &FxHashMap<SequenceName, f64>,
+    name: &SequenceName,
+    counter: f64,
+) -> bool {
+    launch_pulses
+        .get(name)
+        .is_none_or(|pulse| *pulse <= counter + STEP_EPSILON)
+}
+
+/// the step a sequence stepping in step_len's plays on the pulse counter, if one starts on it.
+fn seq_step(counter: f64, step_len: NoteDuration, bpq: f64) -> Option<usize> {
+    let step = next_seq_step(counter, step_len, bpq);
+
+    (next_seq_step(counter + 1., step_len, bpq) > step).then_some(step)
+}
+
+/// adds seqs, replacing the sequences with the same names. returns the change made to each
+/// sequence, as (name, before, after).
+fn insert_sequences(
+    sequences: &mut AllSequences,
+    seqs: Vec<Sequence>,
+) -> Vec<(SequenceName, Option<Sequence>, Option<Sequence>)> {
+    seqs.into_iter()
+        .map(|seq| {
+            let name = seq.name.clone();
+            let before = sequences.insert(name.clone(), seq.clone());
+
+            (name, before, Some(seq))
+        })
+        .collect()
+}
+
+/// the step of a sequence to play at the sequence's own step i.
+fn local_step(
+    step_offsets: &FxHashMap<SequenceName, usize>,
+    name: &SequenceName,
+    i: usize,
+    len: usize,
+) -> usize {
+    // empty sequences are always on their first step.
+    (i + step_offsets.get(name).unwrap_or(&0))
+        .checked_rem(len)
+        .unwrap_or(0)
+}
+
+/// freezes a playing sequence's playhead at the step it would play next.
+#[allow(clippy::too_many_arguments)]
+fn pause_sequence(
+    sequences: &AllSequences,
+    playing_sequences: &mut Vec<SequenceName>,
+    paused_sequences: &mut FxHashMap<SequenceName, usize>,
+    step_offsets: &FxHashMap<SequenceName, usize>,
+    name: SequenceName,
+    counter: f64,
+    bpq: f64,
+    mb_sender: &MbServerHandle,
+    conn: &Uuid,
+) {
+    let Some(sequence) = sequences.get(&name) else {
+        error!("unknown sequence, \"{name}\"");
+        return;
+    };
+
+    let next_step = next_seq_step(counter, sequence.step_len, bpq);
+    let step_n = local_step(step_offsets, &name, next_step, sequence.steps.len());
+
+    playing_sequences.retain(|playing| playing != &name);
+    paused_sequences.insert(name.clone(), step_n);
+
+    let msg = MsgFromServer::SequencePaused {
+        sequence_name: name,
+        step_n,
+    };
+
+    send_msg(mb_sender, conn, msg);
+}
+
+/// continues a paused sequence from the step it was paused on, starting at the next step it
+/// would play after the pulse counter. returns the step it resumed from.
+#[allow(clippy::too_many_arguments)]
+fn resume_sequence(
+    sequences: &AllSequences,
+    playing_sequences: &mut Vec<SequenceName>,
+    step_offsets: &mut FxHashMap<SequenceName, usize>,
+    name: SequenceName,
+    step_n: usize,
+    counter: f64,
+    bpq: f64,
+    mb_sender: &MbServerHandle,
+    conn: &Uuid,
+) -> Option<usize> {
+    let Some(sequence) = sequences.get(&name) else {
+        error!("unknown sequence, \"{name}\"");
+        return None;
+    };
+
+    let next_step = next_seq_step(counter, sequence.step_len, bpq);
+    // treat empty sequences as one step long so they resume from their first step.
+    let len = sequence.steps.len().max(1);
+    let step_n = step_n % len;
+
+    step_offsets.insert(name.clone(), (step_n + len - next_step % len) % len);
+
+    if !playing_sequences.contains(&name) {
+        playing_sequences.push(name.clone());
+    }
+
+    let msg = MsgFromServer::SequenceResumed {
+        sequence_name: name,
+        step_n,
+    };
+
+    send_msg(mb_sender, conn, msg);
+
+    Some(step_n)
+}
+
+/// tells the devices following the midi clock about a transport change.
+fn send_transport(transport: &Sender<Transport>, msg: Transport) {
+    if let Err(e) = transport.send(msg) {
+        error!("failed to send transport change to midi out: {e}");
+    }
+}
+
+/// re-phases the tempo synced automations to the start of the bar.
+fn sync_reset_automations(automation_cmds: &Sender<AutomationCmd>) {
+    if let Err(e) = automation_cmds.send(AutomationCmd::SyncReset) {
+        error!("failed to re-phase automations: {e}");
+    }
+}
+
+/// the pulse the sequencer is at, including how far it is through the current pulse.
+fn pulse_pos(
+    counter: f64,
+    scheduler: &PulseScheduler,
+    clock_source: &ClockSource,
+    tempo: f64,
+    bpq: f64,
+) -> f64 {
+    // the counter is the next pulse to happen.
+    let last_pulse = (counter - 1.).max(0.);
+    let through = match clock_source {
+        ClockSource::Internal() => scheduler
+            .time_until_due()
+            .map_or(1., |left| 1. - left.as_secs_f64() / pulse_len(tempo, bpq))
+            .clamp(0., 1.),
+        // pulses are counted as they arrive, so there is nothing to measure against.
+        ClockSource::External { .. } => 0.,
+    };
+
+    last_pulse + through
+}
+
+/// writes the notes still held & stops recording.
+fn stop_recording(
+    recorder: &mut Option<Recorder>,
+    sequences: &mut AllSequences,
+    step_offsets: &FxHashMap<SequenceName, usize>,
+    pulse: f64,
+    bpq: f64,
+    mb_sender: &MbServerHandle,
+    conn: &Uuid,
+) {
+    let Some(mut recorder) = recorder.take() else {
+        return;
+    };
+
+    if let Some(seq) = sequences.get_mut(&recorder.sequence) {
+        let offset = step_offsets.get(&recorder.sequence).copied().unwrap_or(0);
+
+        recorder.finish(seq, offset, pulse, bpq);
+    }
+
+    let msg = MsgFromServer::RecordingStopped {
+        sequence_name: recorder.sequence,
+    };
+
+    send_msg(mb_sender, conn, msg);
+}
@@ -0,0 +1,137 @@
+use fx_hash::{FxHashMap, FxHashSet};
+use midi_daw_types::{MidiMsg, NoteDuration, RecordMode, Sequence, SequenceName};
+use midi_msg::ChannelVoiceMsg;
+
+/// records the notes played on a midi input into a sequence, quantized to its steps.
+pub struct Recorder {
+    pub sequence: SequenceName,
+    /// the midi input being recorded
+    pub midi_dev: String,
+    mode: RecordMode,
+    /// the global step recording starts on, (after the count-in)
+    start_step: usize,
+    started: bool,
+    /// the notes being held down, with the pulse they started on & their velocity
+    held: FxHashMap<u8, (f64, u8)>,
+    /// the steps replace mode has already cleared
+    cleared: FxHashSet<usize>,
+}
+
+impl Recorder {
+    pub fn new(
+        sequence: SequenceName,
+        midi_dev: String,
+        mode: RecordMode,
+        start_step: usize,
+    ) -> Self {
+        Self {
+            sequence,
+            midi_dev,
+            mode,
+            start_step,
+            started: false,
+            held: FxHashMap::default(),
+            cleared: FxHashSet::default(),
+        }
+    }
+
+    /// true once the count-in is over.
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// called on every step. returns true if recording started on step i.
+    pub fn step(&mut self, i: usize) -> bool {
+        if self.started || i < self.start_step {
+            return false;
+        }
+
+        self.started = true;
+
+        true
+    }
+
+    /// called before a step of the sequence plays. replace mode clears the notes recorded over.
+    pub fn clear_step(&mut self, seq: &mut Sequence, step_i: usize) {
+        if self.mode == RecordMode::Replace
+            && self.cleared.insert(step_i)
+            && let Some(step) = seq.steps.get_mut(step_i)
+        {
+            step.retain(|msg| !matches!(msg, MidiMsg::PlayNote { .. }));
+        }
+    }
+
+    /// records a midi message played at pulse. offset is the sequence's step offset.
+    pub fn handle(
+        &mut self,
+        msg: &midi_msg::MidiMsg,
+        seq: &mut Sequence,
+        offset: usize,
+        pulse: f64,
+        bpq: f64,
+    ) {
+        let midi_msg::MidiMsg::ChannelVoice { msg, .. } = msg else {
+            return;
+        };
+
+        match *msg {
+            // notes played a little early still land on the first step.
+            ChannelVoiceMsg::NoteOn { note, velocity }
+                if velocity > 0 && self.step_at(pulse, bpq) >= self.start_step =>
+            {
+                // a retriggered note ends the one still held.
+                self.note_off(note, seq, offset, pulse, bpq);
+                self.held.insert(note, (pulse, velocity));
+            }
+            // notes played during the count-in aren't recorded.
+            ChannelVoiceMsg::NoteOn { velocity, .. } if velocity > 0 => {}
+            ChannelVoiceMsg::NoteOn { note, .. } | ChannelVoiceMsg::NoteOff { note, .. } => {
+                self.note_off(note, seq, offset, pulse, bpq);
+            }
+            _ => {}
+        }
+    }
+
+    /// writes the notes still held, ending them at pulse.
+    pub fn finish(&mut self, seq: &mut Sequence, offset: usize, pulse: f64, bpq: f64) {
+        let notes: Vec<u8> = self.held.keys().copied().collect();
+
+        notes
+            .into_iter()
+            .for_each(|note| self.note_off(note, seq, offset, pulse, bpq));
+    }
+
+    /// the global step nearest to pulse.
+    fn step_at(&self, pulse: f64, bpq: f64) -> usize {
+        (pulse / (bpq / 4.)).round() as usize
+    }
+
+    /// the step of seq, (counted in its own step length), nearest to pulse.
+    fn seq_step_at(&self, seq: &Sequence, pulse: f64, bpq: f64) -> usize {
+        (pulse / (seq.step_len.quarter_notes() * bpq)).round() as usize
+    }
+
+    fn note_off(&mut self, note: u8, seq: &mut Sequence, offset: usize, pulse: f64, bpq: f64) {
+        let Some((start, velocity)) = self.held.remove(&note) else {
+            return;
+        };
+
+        if seq.steps.is_empty() {
+            return;
+        }
+
+        let step_i = (self.seq_step_at(seq, start, bpq) + offset) % seq.steps.len();
+        let duration = NoteDuration::nearest((pulse - start) / bpq);
+
+        self.clear_step(seq, step_i);
+
+        let step = &mut seq.steps[step_i];
+
+        step.retain(|msg| !matches!(msg, MidiMsg::PlayNote { note: n, .. } if *n == note));
+        step.push(MidiMsg::PlayNote {
+            note,
+            velocity,
+            duration,
+        });
+    }
+}
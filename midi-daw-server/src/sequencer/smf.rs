@@ -0,0 +1,180 @@
+use midi_daw_types::{MidiMsg, Sequence};
+use midly::{
+    Format, Header, MetaMessage, MidiMessage, PitchBend, Smf, Timing, TrackEvent, TrackEventKind,
+    num::{u4, u7, u14, u15, u24, u28},
+};
+
+/// ticks per quarter note in exported midi files.
+const PPQ: u16 = 480;
+
+/// renders sequences to a format 1 standard midi file. the first track holds the tempo, then
+/// every sequence gets a track of its own.
+pub fn to_smf(name: &str, sequences: &[&Sequence], tempo: f64) -> std::io::Result<Vec<u8>> {
+    let mut smf = Smf::new(Header::new(
+        Format::Parallel,
+        Timing::Metrical(u15::new(PPQ)),
+    ));
+    let quarter_note_len = (60_000_000. / tempo).round() as u32;
+
+    smf.tracks.push(vec![
+        meta(0, MetaMessage::TrackName(name.as_bytes())),
+        meta(0, MetaMessage::Tempo(u24::new(quarter_note_len))),
+        meta(0, MetaMessage::TimeSignature(4, 2, 24, 8)),
+        meta(0, MetaMessage::EndOfTrack),
+    ]);
+    smf.tracks.extend(sequences.iter().map(|seq| track(seq)));
+
+    let mut bytes = Vec::new();
+    smf.write_std(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+fn meta(delta: u32, msg: MetaMessage<'_>) -> TrackEvent<'_> {
+    TrackEvent {
+        delta: u28::new(delta),
+        kind: TrackEventKind::Meta(msg),
+    }
+}
+
+/// one loop of a sequence, on the sequence's channel.
+fn track(seq: &Sequence) -> Vec<TrackEvent<'_>> {
+    let channel = u4::new(seq.channel as u8);
+    // the length of one of the sequence's steps in ticks.
+    let step_ticks = seq.step_len.quarter_notes() * PPQ as f64;
+    let mut events: Vec<(u32, MidiMessage)> = Vec::new();
+
+    for (i, step) in seq.steps.iter().enumerate() {
+        let tick = (i as f64 * step_ticks).round() as u32;
+
+        for msg in step {
+            match *msg {
+                MidiMsg::PlayNote {
+                    note,
+                    velocity,
+                    duration,
+                } => {
+                    let len = (duration.quarter_notes() * PPQ as f64).round().max(1.) as u32;
+                    let key = u7::new(note);
+
+                    events.push((
+                        tick,
+                        MidiMessage::NoteOn {
+                            key,
+                            vel: u7::new(velocity),
+                        },
+                    ));
+                    events.push((
+                        tick + len,
+                        MidiMessage::NoteOff {
+                            key,
+                            vel: u7::new(0),
+                        },
+                    ));
+                }
+                MidiMsg::StopNote { note } => events.push((
+                    tick,
+                    MidiMessage::NoteOff {
+                        key: u7::new(note),
+                        vel: u7::new(0),
+                    },
+                )),
+                MidiMsg::CC { control, value } => events.push((
+                    tick,
+                    MidiMessage::Controller {
+                        controller: u7::new(control),
+                        value: u7::new(value),
+                    },
+                )),
+                MidiMsg::PitchBend { bend } => events.push((
+                    tick,
+                    MidiMessage::PitchBend {
+                        bend: PitchBend(u14::new(bend)),
+                    },
+                )),
+            }
+        }
+    }
+
+    // on the same tick, notes end before the CCs & notes that follow them.
+    events.sort_by_key(|(tick, msg)| {
+        let order = match msg {
+            MidiMessage::NoteOff { .. } => 0,
+            MidiMessage::NoteOn { .. } => 2,
+            _ => 1,
+        };
+
+        (*tick, order)
+    });
+
+    let mut track = vec![meta(0, MetaMessage::TrackName(seq.name.as_bytes()))];
+    let mut last_tick = 0;
+
+    for (tick, message) in events {
+        track.push(TrackEvent {
+            delta: u28::new(tick - last_tick),
+            kind: TrackEventKind::Midi { channel, message },
+        });
+        last_tick = tick;
+    }
+
+    // the track lasts the whole loop, even if it ends in silence.
+    let end = ((seq.steps.len() as f64 * step_ticks).round() as u32).max(last_tick);
+    track.push(meta(end - last_tick, MetaMessage::EndOfTrack));
+
+    track
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use midi_daw_types::{MidiChannel, NoteDuration, smf::sequences_from_smf};
+
+    fn note(note: u8, duration: NoteDuration) -> MidiMsg {
+        MidiMsg::PlayNote {
+            note,
+            velocity: 100,
+            duration,
+        }
+    }
+
+    #[test]
+    fn sequences_survive_a_round_trip() {
+        let mut bass = Sequence::new("bass".into());
+        bass.channel = MidiChannel::Ch3;
+        bass.steps[0] = vec![MidiMsg::CC {
+            control: 74,
+            value: 20,
+        }];
+        bass.steps[0].push(note(36, NoteDuration::En(1)));
+        bass.steps[6] = vec![note(43, NoteDuration::Qn(1))];
+        bass.steps[12] = vec![MidiMsg::PitchBend { bend: 9000 }];
+        // the last note rings over the end of the loop.
+        bass.steps[15] = vec![note(36, NoteDuration::Hn(1))];
+
+        let mut drums = Sequence::new("drums".into());
+        drums.channel = MidiChannel::Ch10;
+        drums.steps[4] = vec![note(36, NoteDuration::Sn(1)), note(42, NoteDuration::Sn(1))];
+
+        let bytes = to_smf("song", &[&bass, &drums], 120.).unwrap();
+        let sequences = sequences_from_smf("song", &bytes).unwrap();
+
+        assert_eq!(sequences.len(), 2);
+        // the note that rings over the end of the loop makes the imported bass a bar longer.
+        assert_eq!(sequences[0].steps.len(), 32);
+        assert_eq!(sequences[1].steps.len(), 16);
+
+        for (old, new) in [&bass, &drums].into_iter().zip(&sequences) {
+            assert_eq!(new.name, old.name);
+            assert_eq!(new.channel, old.channel);
+
+            for (i, (old_step, new_step)) in old.steps.iter().zip(&new.steps).enumerate() {
+                let (mut old_step, mut new_step) = (old_step.clone(), new_step.clone());
+                old_step.sort();
+                new_step.sort();
+
+                assert_eq!(new_step, old_step, "{}, step {i}", old.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use async_std::{
+    fs::{File, create_dir_all, read_dir, remove_file},
+    io::{BufReader, ReadExt, WriteExt},
+};
+use futures_lite::stream::StreamExt;
+use midi_daw_types::{Project, Sequence, SequencerError};
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+use tracing::*;
+use xdg::BaseDirectories;
+
+/// the sub directory of the data directory projects are saved in.
+pub const PROJECTS_DIR: &str = "projects";
+
+pub async fn save_sequence(seq: &Sequence) -> Result<(), SequencerError> {
+    let json = serde_json::to_string(seq)?;
+
+    write_saved(None, &seq.name, &json).await?;
+    info!("saved '{}' to file {}.json.", seq.name, seq.name);
+
+    Ok(())
+}
+
+pub async fn load_sequence(name: &str) -> Result<Sequence, SequencerError> {
+    let json_text = read_saved(None, name).await?;
+
+    Ok(serde_json::from_str(&json_text)?)
+}
+
+pub async fn save_project(project_name: &str, project: &Project) -> Result<(), SequencerError> {
+    let json = serde_json::to_string(project)?;
+
+    write_saved(Some(PROJECTS_DIR), project_name, &json).await?;
+    info!("saved '{project_name}' to file {project_name}.json.");
+
+    Ok(())
+}
+
+pub async fn load_project(project_name: &str) -> Result<Project, SequencerError> {
+    let json_text = read_saved(Some(PROJECTS_DIR), project_name).await?;
+    let project = Project::from_json(&json_text)?;
+
+    Ok(project)
+}
+
+/// the midi-daw data directory, or sub_dir in it.
+fn data_dir(sub_dir: Option<&str>) -> Result<PathBuf, SequencerError> {
+    let mut data_dir = BaseDirectories::new()
+        .data_home
+        .ok_or(SequencerError::NoDataDir)?;
+    data_dir.push("midi-daw");
+
+    if let Some(sub_dir) = sub_dir {
+        data_dir.push(sub_dir);
+    }
+
+    Ok(data_dir)
+}
+
+/// the path of the json file name is saved to in the data directory, (or sub_dir in it). names
+/// that could point outside of the data directory are an error.
+fn saved_path(sub_dir: Option<&str>, name: &str) -> Result<PathBuf, SequencerError> {
+    check_name(name)?;
+
+    let mut path = data_dir(sub_dir)?;
+
+    if name.ends_with(".json") {
+        path.push(name);
+    } else {
+        path.push(format!("{name}.json"));
+    }
+
+    Ok(path)
+}
+
+/// checks that name is a plain file name, so it can only refer to a file directly in the
+/// directory it's saved to. names starting with a "." are refused so they can't be "..", or hide.
+fn check_name(name: &str) -> Result<(), SequencerError> {
+    let file_name = name.strip_suffix(".json").unwrap_or(name);
+    let mut components = Path::new(file_name).components();
+    let is_file_name = matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    );
+
+    if is_file_name
+        && !file_name.starts_with('.')
+        && !file_name.contains(['/', '\\'])
+        && !file_name.chars().any(char::is_control)
+    {
+        Ok(())
+    } else {
+        Err(SequencerError::InvalidName { name: name.into() })
+    }
+}
+
+/// the names of the files saved in the data directory, (or sub_dir in it).
+pub async fn list_saved(sub_dir: Option<&str>) -> Result<Vec<String>, SequencerError> {
+    let dir = data_dir(sub_dir)?;
+
+    // nothing has been saved yet.
+    if !dir.exists() {
+        return Ok(Vec::default());
+    }
+
+    let mut dir_contents = read_dir(dir).await?;
+    let mut contents = Vec::default();
+
+    while let Some(f_name) = dir_contents.next().await {
+        let fname = f_name?;
+
+        if fname.file_type().await?.is_file() {
+            contents.push(fname.file_name().to_string_lossy().to_string())
+        }
+    }
+
+    Ok(contents)
+}
+
+/// reads the json that name was saved as.
+async fn read_saved(sub_dir: Option<&str>, name: &str) -> Result<String, SequencerError> {
+    let path = saved_path(sub_dir, name)?;
+    let file = File::open(&path).await.map_err(|e| match e.kind() {
+        ErrorKind::NotFound => SequencerError::NotSaved { name: name.into() },
+        _ => e.into(),
+    })?;
+    let mut json_text = String::new();
+
+    BufReader::new(file).read_to_string(&mut json_text).await?;
+
+    Ok(json_text)
+}
+
+/// saves json as name, making the directory it goes in if it doesn't exist.
+async fn write_saved(sub_dir: Option<&str>, name: &str, json: &str) -> Result<(), SequencerError> {
+    let dir = data_dir(sub_dir)?;
+
+    if !dir.exists() {
+        create_dir_all(&dir).await?;
+    }
+
+    let mut file = File::create(saved_path(sub_dir, name)?).await?;
+    file.write_all(json.as_bytes()).await?;
+    file.flush().await?;
+
+    Ok(())
+}
+
+/// removes the json file that name was saved as.
+pub async fn rm_saved(sub_dir: Option<&str>, name: &str) -> Result<(), SequencerError> {
+    let path = saved_path(sub_dir, name)?;
+
+    if !path.exists() {
+        return Err(SequencerError::NotSaved { name: name.into() });
+    }
+
+    remove_file(&path).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BAD_NAMES: [&str; 12] = [
+        "",
+        ".json",
+        ".",
+        "..",
+        "../song",
+        "../../.bashrc",
+        "songs/../../song",
+        "/etc/passwd",
+        "..\\song",
+        ".hidden",
+        "song/",
+        "so\0ng",
+    ];
+
+    #[test]
+    fn plain_names_are_ok() {
+        for name in [
+            "song",
+            "song.json",
+            "my song 2",
+            "drums (take 3)",
+            "Midi Through:0",
+        ] {
+            assert_eq!(check_name(name), Ok(()), "{name:?} should be valid");
+        }
+    }
+
+    #[test]
+    fn path_like_names_are_refused() {
+        for name in BAD_NAMES {
+            assert_eq!(
+                check_name(name),
+                Err(SequencerError::InvalidName { name: name.into() }),
+                "{name:?} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn saved_paths_stay_in_the_data_dir() {
+        for sub_dir in [None, Some(PROJECTS_DIR)] {
+            let Ok(dir) = data_dir(sub_dir) else {
+                return;
+            };
+            let path = saved_path(sub_dir, "song").unwrap();
+
+            assert_eq!(path.parent(), Some(dir.as_path()));
+            assert_eq!(path.file_name(), Some("song.json".as_ref()));
+            assert!(
+                BAD_NAMES
+                    .iter()
+                    .all(|name| saved_path(sub_dir, name).is_err())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn bad_names_never_touch_the_disk() {
+        let invalid = |name: &str| SequencerError::InvalidName { name: name.into() };
+
+        for name in BAD_NAMES {
+            assert_eq!(read_saved(None, name).await, Err(invalid(name)));
+            assert_eq!(rm_saved(None, name).await, Err(invalid(name)));
+            assert_eq!(load_project(name).await, Err(invalid(name)));
+            assert_eq!(rm_saved(Some(PROJECTS_DIR), name).await, Err(invalid(name)));
+        }
+
+        let err = invalid("../song");
+
+        // the server responds to these with a 400.
+        assert!(err.is_client_error() && !err.is_not_found());
+    }
+}
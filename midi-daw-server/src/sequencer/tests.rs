@@ -0,0 +1,164 @@
+use super::*;
+use crate::server::message_bus::MbServer;
+use midi_daw_types::GrooveStep;
+
+fn sequences(seqs: impl IntoIterator<Item = Sequence>) -> AllSequences {
+    seqs.into_iter()
+        .map(|seq| (seq.name.clone(), seq))
+        .collect()
+}
+
+#[test]
+fn empty_sequences_pause_and_resume() {
+    let (_mb_server, mb_sender) = MbServer::new();
+    let conn = Uuid::new_v4();
+    let mut empty = Sequence::new("empty".into());
+    empty.steps.clear();
+    let sequences = sequences([empty]);
+    let mut playing = vec!["empty".to_string()];
+    let mut paused = FxHashMap::default();
+    let mut step_offsets = FxHashMap::default();
+
+    pause_sequence(
+        &sequences,
+        &mut playing,
+        &mut paused,
+        &step_offsets,
+        "empty".into(),
+        100.,
+        24.,
+        &mb_sender,
+        &conn,
+    );
+
+    assert!(playing.is_empty());
+    assert_eq!(paused.get("empty"), Some(&0));
+
+    let step_n = resume_sequence(
+        &sequences,
+        &mut playing,
+        &mut step_offsets,
+        "empty".into(),
+        paused["empty"],
+        200.,
+        24.,
+        &mb_sender,
+        &conn,
+    );
+
+    assert_eq!(step_n, Some(0));
+    assert_eq!(playing, vec!["empty".to_string()]);
+}
+
+#[test]
+fn overwriting_imports_undo_together() {
+    let mut sequences = sequences([Sequence::new("bass".into())]);
+    let mut bass = Sequence::new("bass".into());
+    bass.channel = MidiChannel::Ch2;
+    let mut history = History::default();
+
+    let changes = insert_sequences(&mut sequences, vec![bass, Sequence::new("drums".into())]);
+    history.record_changes(changes);
+
+    // one undo removes the new drums & brings back the old bass.
+    let cmds = history.undo().map(Edit::into_cmds).unwrap_or_default();
+
+    assert!(matches!(
+        &cmds[..],
+        [
+            SequencerControlCmd::RmSequence { name },
+            SequencerControlCmd::RestoreSequence(seq),
+        ] if name == "drums" && seq.channel == MidiChannel::Ch1
+    ));
+    assert!(history.undo().is_none());
+}
+
+#[test]
+fn queueing_twice_queues_once() {
+    let (_mb_server, mb_sender) = MbServer::new();
+    let conn = Uuid::new_v4();
+    let bass = Sequence::new("bass".into());
+    let mut queued = Vec::new();
+    let mut step_offsets = FxHashMap::default();
+    let mut launch_pulses = FxHashMap::default();
+
+    for counter in [10., 20.] {
+        queue_start(
+            &bass,
+            LaunchQuant::Beat(),
+            counter,
+            24.,
+            &[],
+            &mut queued,
+            &mut step_offsets,
+            &mut launch_pulses,
+            &mb_sender,
+            &conn,
+        );
+    }
+
+    assert_eq!(queued, vec!["bass".to_string()]);
+    assert_eq!(launch_pulses.get("bass"), Some(&24.));
+}
+
+#[test]
+fn playing_a_running_sequence_keeps_its_phase() {
+    let (_mb_server, mb_sender) = MbServer::new();
+    let conn = Uuid::new_v4();
+    let bass = Sequence::new("bass".into());
+    let playing = vec!["bass".to_string()];
+    let mut queued = Vec::new();
+    let mut step_offsets = FxHashMap::from_iter([("bass".to_string(), 3)]);
+    let mut launch_pulses = FxHashMap::default();
+
+    queue_start(
+        &bass,
+        LaunchQuant::Beat(),
+        10.,
+        24.,
+        &playing,
+        &mut queued,
+        &mut step_offsets,
+        &mut launch_pulses,
+        &mb_sender,
+        &conn,
+    );
+
+    assert!(queued.is_empty());
+    assert_eq!(step_offsets.get("bass"), Some(&3));
+    assert!(launch_pulses.is_empty());
+}
+
+#[test]
+fn grooves_pull_steps_back_to_the_step() {
+    let mut seq = Sequence::new("drums".into());
+    seq.swing = 50;
+    seq.groove = Some(Groove::new(vec![
+        GrooveStep::new(-2, 100),
+        GrooveStep::new(-2, 100),
+    ]));
+
+    // the first step isn't swung, so it can't be pulled any earlier.
+    assert_eq!(step_delay(&seq, 0, 24.), 0.);
+    // the second is swung by half a step, (3 pulses), & pulled back by 2.
+    assert_eq!(step_delay(&seq, 1, 24.), 1.);
+}
+
+#[test]
+fn failed_undos_stay_undoable() {
+    let mut history = History::default();
+    history.record(Edit::Renamed {
+        old_name: "bass".into(),
+        new_name: "lead".into(),
+    });
+
+    // "bass" was taken since, so renaming back fails.
+    assert!(history.undo().is_some());
+    history.replay_failed();
+
+    assert!(history.redo().is_none());
+    assert!(matches!(
+        history.undo(),
+        Some(Edit::Renamed { old_name, new_name }) if old_name == "lead" && new_name == "bass"
+    ));
+}
@@ -0,0 +1,451 @@
+use actix_web::{
+    Error, HttpMessage, HttpResponse,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{Method, header},
+    middleware::Next,
+    web,
+};
+use serde::Deserialize;
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+use tracing::{Span, field::Empty, info_span, log::*};
+use tracing_actix_web::{DefaultRootSpanBuilder, RequestId, RootSpanBuilder};
+use xdg::BaseDirectories;
+
+/// posts that only read, (they take a body so they can't be gets).
+const READ_ONLY_POSTS: [&str; 2] = ["/sequence/export-smf", "/project/export-smf"];
+
+/// what a client is allowed to do.
+#[derive(Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    /// can do anything
+    Edit,
+    /// can watch the steps & read sequences, but can't change anything
+    ReadOnly,
+}
+
+impl Role {
+    /// true if the role can make a method request to path.
+    fn allows(self, method: &Method, path: &str) -> bool {
+        match self {
+            Self::Edit => true,
+            Self::ReadOnly => {
+                matches!(*method, Method::GET | Method::HEAD) || READ_ONLY_POSTS.contains(&path)
+            }
+        }
+    }
+}
+
+/// a token & the role it grants.
+#[derive(Deserialize, Clone, Debug)]
+struct TokenConf {
+    token: String,
+    role: Role,
+}
+
+/// the auth config file.
+#[derive(Deserialize, Clone, Debug)]
+struct AuthConf {
+    tokens: Vec<TokenConf>,
+}
+
+/// the tokens clients on the network have to send, (None if auth is off).
+#[derive(Clone, Debug, Default)]
+pub struct Auth {
+    tokens: Option<Vec<TokenConf>>,
+}
+
+impl Auth {
+    /// reads the auth config from `$XDG_CONFIG_HOME/midi-daw/auth.json`. auth is off if the file
+    /// doesn't exist. a broken file is an error, so the server doesn't start up open by mistake.
+    pub fn load() -> io::Result<Self> {
+        let Some(path) = config_path() else {
+            warn!("no config directory, the network api is open to everyone");
+            return Ok(Self::default());
+        };
+
+        let json_text = match fs::read_to_string(&path) {
+            Ok(json_text) => json_text,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                warn!("no auth config at {path:?}, the network api is open to everyone");
+                return Ok(Self::default());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let conf: AuthConf = serde_json::from_str(&json_text).map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("reading auth config, {path:?}, failed with error: {e}"),
+            )
+        })?;
+        let (tokens, empty): (Vec<_>, Vec<_>) = conf
+            .tokens
+            .into_iter()
+            .partition(|conf| !conf.token.is_empty());
+
+        if !empty.is_empty() {
+            warn!("ignoring {} empty tokens in {path:?}", empty.len());
+        }
+
+        info!("token auth is on, loaded {} tokens", tokens.len());
+
+        Ok(Self {
+            tokens: Some(tokens),
+        })
+    }
+
+    /// the role of a client that sent token, None if it isn't let in at all.
+    pub fn role(&self, token: Option<&str>) -> Option<Role> {
+        let Some(tokens) = self.tokens.as_ref() else {
+            return Some(Role::Edit);
+        };
+        let token = token?;
+
+        tokens
+            .iter()
+            .find(|conf| same_token(&conf.token, token))
+            .map(|conf| conf.role)
+    }
+}
+
+/// middleware that checks the token of requests from the network & stores the client's Role in
+/// the request. requests over the unix socket, (which have no peer address), are trusted.
+pub async fn check_auth<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let role = if req.peer_addr().is_none() {
+        Some(Role::Edit)
+    } else {
+        req.app_data::<web::Data<Auth>>()
+            .and_then(|auth| auth.role(request_token(&req).as_deref()))
+    };
+
+    let Some(role) = role else {
+        warn!(
+            "refused {} {}, the token was missing or wrong",
+            req.method(),
+            req.path()
+        );
+        let res = HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .body("a valid token is required");
+
+        return Ok(req.into_response(res).map_into_right_body());
+    };
+
+    if !role.allows(req.method(), req.path()) {
+        warn!(
+            "refused {} {}, the token is read only",
+            req.method(),
+            req.path()
+        );
+        let res = HttpResponse::Forbidden().body("this token is read only");
+
+        return Ok(req.into_response(res).map_into_right_body());
+    }
+
+    req.extensions_mut().insert(role);
+
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+/// the span TracingLogger logs each request in. it's tracing_actix_web's default span, but the
+/// target is only the path, because the message bus's query string can hold a token.
+pub struct NoQuerySpan;
+
+impl RootSpanBuilder for NoQuerySpan {
+    fn on_request_start(req: &ServiceRequest) -> Span {
+        let route = req.match_pattern().unwrap_or_else(|| "default".into());
+        let method = req.method().as_str();
+        let user_agent = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        let request_id = req.extensions().get::<RequestId>().copied();
+        let connection_info = req.connection_info();
+
+        info_span!(
+            "HTTP request",
+            http.method = %method,
+            http.route = %route,
+            http.flavor = ?req.version(),
+            http.scheme = %connection_info.scheme(),
+            http.host = %connection_info.host(),
+            http.client_ip = %connection_info.realip_remote_addr().unwrap_or(""),
+            http.user_agent = %user_agent,
+            http.target = %req.path(),
+            http.status_code = Empty,
+            otel.name = %format!("{method} {route}"),
+            otel.kind = "server",
+            otel.status_code = Empty,
+            request_id = ?request_id,
+            exception.message = Empty,
+            exception.details = Empty,
+        )
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome);
+    }
+}
+
+/// the token query parameter.
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// the token a request was sent with, from its "Authorization: Bearer" header. the message bus
+/// can also take it as a "token" query parameter, because browsers can't set websocket headers.
+fn request_token(req: &ServiceRequest) -> Option<String> {
+    let header_token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+
+    header_token.or_else(|| {
+        (req.path() == "/message-bus")
+            .then(|| web::Query::<TokenQuery>::from_query(req.query_string()).ok())
+            .flatten()
+            .and_then(|query| query.into_inner().token)
+    })
+}
+
+/// compares the given token to a configured one without stopping at the first difference, so the
+/// time taken doesn't give away the configured token, or its length. the time only depends on the
+/// length of the given token.
+fn same_token(token: &str, given: &str) -> bool {
+    let (token, given) = (token.as_bytes(), given.as_bytes());
+
+    given
+        .iter()
+        .enumerate()
+        .fold(token.len() ^ given.len(), |diff, (i, byte)| {
+            // wraps around a shorter token, (the length difference has already failed the match).
+            let expected = token.get(i % token.len().max(1)).copied().unwrap_or(0);
+
+            diff | (expected ^ byte) as usize
+        })
+        == 0
+}
+
+fn config_path() -> Option<PathBuf> {
+    let mut path = BaseDirectories::new().config_home?;
+    path.push("midi-daw");
+    path.push("auth.json");
+
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        App,
+        http::StatusCode,
+        middleware::from_fn,
+        test::{TestRequest, call_service, init_service, read_body},
+    };
+    use std::sync::{Arc, Mutex};
+    use tracing_actix_web::TracingLogger;
+    use tracing_subscriber::fmt::{MakeWriter, format::FmtSpan};
+
+    const EDIT_TOKEN: &str = "edit-token";
+    const READ_TOKEN: &str = "read-token";
+
+    fn with_tokens() -> Auth {
+        Auth {
+            tokens: Some(vec![
+                TokenConf {
+                    token: EDIT_TOKEN.into(),
+                    role: Role::Edit,
+                },
+                TokenConf {
+                    token: READ_TOKEN.into(),
+                    role: Role::ReadOnly,
+                },
+            ]),
+        }
+    }
+
+    /// sends req to an app checked by auth. the message bus responds with the client's role.
+    async fn send(auth: Auth, req: TestRequest) -> ServiceResponse {
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(auth))
+                .wrap(from_fn(check_auth))
+                .route("/sequence/new", web::post().to(HttpResponse::Ok))
+                .route("/sequence/export-smf", web::post().to(HttpResponse::Ok))
+                .route("/sequence/names", web::get().to(HttpResponse::Ok))
+                .route(
+                    "/message-bus",
+                    web::get().to(async |role: web::ReqData<Role>| format!("{:?}", *role)),
+                ),
+        )
+        .await;
+
+        call_service(&app, req.to_request())
+            .await
+            .map_into_boxed_body()
+    }
+
+    /// req as if it came from another device on the network, with token.
+    fn lan_req(req: TestRequest, token: Option<&str>) -> TestRequest {
+        let req = req.peer_addr("192.168.1.20:50000".parse().unwrap());
+
+        match token {
+            Some(token) => req.insert_header((header::AUTHORIZATION, format!("Bearer {token}"))),
+            None => req,
+        }
+    }
+
+    #[actix_web::test]
+    async fn everything_is_open_without_a_config() {
+        let req = lan_req(TestRequest::post().uri("/sequence/new"), None);
+
+        assert_eq!(send(Auth::default(), req).await.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn network_requests_need_a_known_token() {
+        for token in [None, Some("wrong-token"), Some("")] {
+            let req = lan_req(TestRequest::get().uri("/sequence/names"), token);
+            let res = send(with_tokens(), req).await;
+
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "token: {token:?}");
+            assert!(res.headers().contains_key(header::WWW_AUTHENTICATE));
+        }
+
+        let req = lan_req(TestRequest::post().uri("/sequence/new"), Some(EDIT_TOKEN));
+
+        assert_eq!(send(with_tokens(), req).await.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn read_only_tokens_cant_edit() {
+        let requests = [
+            (TestRequest::get().uri("/sequence/names"), StatusCode::OK),
+            (
+                TestRequest::post().uri("/sequence/export-smf"),
+                StatusCode::OK,
+            ),
+            (
+                TestRequest::post().uri("/sequence/new"),
+                StatusCode::FORBIDDEN,
+            ),
+        ];
+
+        for (req, status) in requests {
+            let res = send(with_tokens(), lan_req(req, Some(READ_TOKEN))).await;
+
+            assert_eq!(res.status(), status);
+        }
+    }
+
+    #[actix_web::test]
+    async fn the_unix_socket_is_trusted() {
+        let req = TestRequest::post().uri("/sequence/new");
+
+        assert_eq!(send(with_tokens(), req).await.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn the_message_bus_takes_a_query_token() {
+        let req = lan_req(
+            TestRequest::get().uri(&format!("/message-bus?token={READ_TOKEN}")),
+            None,
+        );
+        let res = send(with_tokens(), req).await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "ReadOnly");
+
+        // only the message bus takes the token as a query parameter.
+        let req = lan_req(
+            TestRequest::get().uri(&format!("/sequence/names?token={EDIT_TOKEN}")),
+            None,
+        );
+
+        assert_eq!(
+            send(with_tokens(), req).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    /// collects what a tracing subscriber writes.
+    #[derive(Clone, Default)]
+    struct Logs(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Logs {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[actix_web::test]
+    async fn query_tokens_arent_logged() {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(logs.clone())
+            .with_span_events(FmtSpan::CLOSE)
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = init_service(
+            App::new()
+                .wrap(TracingLogger::<NoQuerySpan>::new())
+                .route("/message-bus", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = TestRequest::get().uri(&format!("/message-bus?token={EDIT_TOKEN}"));
+        let res = call_service(&app, req.to_request()).await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        drop(res);
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+
+        assert!(logs.contains("/message-bus"), "logs: {logs}");
+        assert!(!logs.contains(EDIT_TOKEN), "logs: {logs}");
+    }
+
+    #[test]
+    fn tokens_only_match_exactly() {
+        assert!(same_token(EDIT_TOKEN, EDIT_TOKEN));
+        assert!(!same_token(EDIT_TOKEN, &EDIT_TOKEN[1..]));
+        assert!(!same_token(
+            EDIT_TOKEN,
+            &format!("{EDIT_TOKEN}{EDIT_TOKEN}")
+        ));
+        assert!(!same_token(EDIT_TOKEN, ""));
+        assert!(!same_token("", EDIT_TOKEN));
+        assert!(!same_token("ab", "ac"));
+    }
+}
@@ -0,0 +1,60 @@
+use crate::sequencer::SequencerReq;
+use actix_web::HttpResponse;
+use crossbeam::channel::{Sender, TrySendError};
+use std::time::Duration;
+use tokio::{sync::oneshot, time::timeout};
+use tracing::log::*;
+
+/// how long a handler waits for the sequencer to reply before giving up.
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+/// how many seconds a client should wait before retrying when the sequencer is busy.
+const RETRY_AFTER_SECS: u64 = 1;
+
+/// sends a request to the sequencer & waits for its reply without blocking the worker thread.
+/// mk_req makes the request from the responder the sequencer replies on. if the sequencer's
+/// queue is full the error is a 503, (the request wasn't queued, so it's safe to retry). if it
+/// was queued but the sequencer doesn't reply within REPLY_TIMEOUT, the error is a 504, (it may
+/// still be carried out, so retrying could do it twice).
+pub async fn ask<T>(
+    seq_coms: &Sender<SequencerReq>,
+    mk_req: impl FnOnce(oneshot::Sender<T>) -> SequencerReq,
+) -> Result<T, HttpResponse> {
+    let (responder, recv_er) = oneshot::channel();
+
+    match seq_coms.try_send(mk_req(responder)) {
+        Ok(_) => {}
+        Err(TrySendError::Full(_)) => {
+            warn!("the sequencer's control queue is full");
+
+            return Err(unavailable("the sequencer is busy"));
+        }
+        Err(TrySendError::Disconnected(_)) => {
+            let error_msg = "sending control message to sequencer failed, it has stopped";
+
+            error!("{error_msg}");
+            return Err(HttpResponse::InternalServerError().body(error_msg));
+        }
+    }
+
+    match timeout(REPLY_TIMEOUT, recv_er).await {
+        Ok(Ok(res)) => Ok(res),
+        Ok(Err(e)) => {
+            let error_msg = format!("reading reponse from sequencer failed with error, {e}");
+
+            error!("{error_msg}");
+            Err(HttpResponse::InternalServerError().body(error_msg))
+        }
+        Err(_) => {
+            let error_msg = format!("the sequencer didn't reply within {REPLY_TIMEOUT:?}");
+
+            warn!("{error_msg}");
+            Err(HttpResponse::GatewayTimeout().body(error_msg))
+        }
+    }
+}
+
+fn unavailable(error_msg: &str) -> HttpResponse {
+    HttpResponse::ServiceUnavailable()
+        .insert_header(("Retry-After", RETRY_AFTER_SECS.to_string()))
+        .body(error_msg.to_string())
+}
@@ -1,3 +1,4 @@
+use crate::server::auth::Role;
 use actix_web::{
     Error, HttpRequest, HttpResponse, get,
     web::{self, Bytes},
@@ -189,6 +190,7 @@ async fn do_message_bus(
     chat_server: MbServerHandle,
     mut session: actix_ws::Session,
     msg_stream: actix_ws::MessageStream,
+    role: Role,
 ) {
     let id = Uuid::new_v4();
 
@@ -215,6 +217,12 @@ async fn do_message_bus(
                 // receive messages from websocket
                 Some(Ok(msg)) = msg_stream.next() => {
                     match msg {
+                        // read only clients can only listen.
+                        AggregatedMessage::Text(_) | AggregatedMessage::Binary(_)
+                            if role == Role::ReadOnly =>
+                        {
+                            warn!("dropped a message from read only connection, {id}");
+                        }
                         AggregatedMessage::Text(text) => {
                             // text message
                             // session.text(text).await.unwrap();
@@ -270,11 +278,19 @@ pub async fn message_bus(
     req: HttpRequest,
     stream: web::Payload,
     chat_server: web::Data<MbServerHandle>,
+    role: Option<web::ReqData<Role>>,
 ) -> Result<HttpResponse, Error> {
     let (res, session, msg_stream) = actix_ws::handle(&req, stream)?;
+    // no role means auth isn't checked on this server.
+    let role = role.map_or(Role::Edit, |role| *role);
 
     // spawn websocket handler (and don't await it) so that the response is returned immediately
-    spawn_local(do_message_bus((**chat_server).clone(), session, msg_stream));
+    spawn_local(do_message_bus(
+        (**chat_server).clone(),
+        session,
+        msg_stream,
+        role,
+    ));
 
     Ok(res)
 }
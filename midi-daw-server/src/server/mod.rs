@@ -1,125 +1,125 @@
 use crate::{
-    midi::{MidiDev, dev::fmt_dev_name},
-    sequencer::SequencerControlCmd,
+    midi::{
+        MidiDev, VirtualDevs,
+        automation::{AutomationCmd, make_automation},
+        dev::{fmt_dev_name, is_own_port},
+        out::unwrap_rw_lock,
+        thru::Routes,
+    },
+    sequencer::{SequencerControlCmd, SequencerReq},
     server::{
+        auth::{Auth, NoQuerySpan, check_auth},
+        bridge::ask,
         message_bus::{MbServer, MbServerHandle},
-        note::{pitch_bend, play_note, send_cc, stop_note},
     },
 };
 use actix::spawn;
 use actix_web::{
-    App, HttpResponse, HttpResponseBuilder, HttpServer, get, post,
+    App, HttpResponse, HttpResponseBuilder, HttpServer, get,
+    http::header::{Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue},
+    middleware::from_fn,
+    post,
     web::{self, Json},
 };
 use crossbeam::channel::Sender;
 use futures::future::join_all;
-use fx_hash::FxHashSet;
 use midi_daw_types::{
-    AddNoteBody, ChangeLenByBody, GetSequenceQuery, MidiMsg, MidiReqBody, NoteDuration,
-    RenameSequenceBody, RmNoteBody, SetChannelBody, SetDevBody, UDS_SERVER_PATH,
+    AddCmdBody, AddNoteBody, Arrangement, ChangeLenByBody, ClockSource, ErrorBody,
+    GetSequenceQuery, ImportSmfBody, LaunchBody, LaunchQuant, LaunchSceneBody, MidiClockOutBody,
+    MidiReqBody, NoteDuration, RecordBody, RenameSequenceBody, RmCmdBody, RmNoteBody, Route, Scene,
+    SequencerError, SetChannelBody, SetDevBody, SetGrooveBody, SetStepLenBody, SetSwingBody,
+    StartAutomationBody, UDS_SERVER_PATH, smf::sequences_from_smf,
 };
 pub use midi_daw_types::{BPQ, Tempo};
-use midir::MidiOutput;
-use tokio::{
-    sync::{Mutex, oneshot},
-    task::spawn_local,
-};
+use midir::{MidiInput, MidiOutput};
+use tokio::sync::{oneshot, watch};
 use tracing::log::*;
 use tracing_actix_web::TracingLogger;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
+mod auth;
+mod bridge;
 pub mod message_bus;
 mod note;
+#[cfg(test)]
+mod tests;
 
 pub type MidiOut = Sender<(String, midi_msg::MidiMsg)>;
+/// midi requests for midi out to play, (notes are ended on the pulse clock).
+pub type MidiReqs = Sender<MidiReqBody>;
+/// the last sync pulse midi out handled.
+pub type PulseWatch = watch::Receiver<u64>;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
 pub struct BPQMarker;
 
+/// responds with a command's error as json. 404 if the command refers to something that doesn't
+/// exist, 409 if it would replace something, 400 if it was bad otherwise, or 500 if the server
+/// failed.
+fn error_response(e: SequencerError) -> HttpResponse {
+    let mut res = if e.is_not_found() {
+        HttpResponse::NotFound()
+    } else if e.is_conflict() {
+        HttpResponse::Conflict()
+    } else if e.is_client_error() {
+        HttpResponse::BadRequest()
+    } else {
+        HttpResponse::InternalServerError()
+    };
+
+    res.json(ErrorBody::from(e))
+}
+
+/// sends msg to the sequencer & responds once it's been carried out, (or the sequencer is too
+/// busy to).
+async fn sequencer_cmd(seq_coms: &Sender<SequencerReq>, msg: SequencerControlCmd) -> HttpResponse {
+    match ask(seq_coms, |responder| (msg, Some(responder))).await {
+        Ok(Ok(())) => HttpResponse::Ok().finish(),
+        Ok(Err(e)) => error_response(e),
+        Err(res) => res,
+    }
+}
+
 #[post("/midi")]
 async fn midi(
-    tempo: web::Data<Tempo>,
-    midi_out: web::Data<MidiOut>,
+    bpq: web::Data<(BPQMarker, BPQ)>,
+    midi_reqs: web::Data<MidiReqs>,
+    pulse_watch: web::Data<PulseWatch>,
     req_body: Json<MidiReqBody>,
 ) -> HttpResponseBuilder {
-    let dev = req_body.midi_dev.clone();
-    let channel = req_body.channel;
-
-    match req_body.msg {
-        MidiMsg::PlayNote {
-            note,
-            velocity,
-            duration,
-        } => {
-            if let Ok(tempo) = tempo.read() {
-                play_note(*tempo, midi_out, dev, channel, note, velocity, duration).await;
-            }
-        }
-        MidiMsg::StopNote { note } => stop_note(midi_out, dev, channel, note).await,
-        MidiMsg::CC { control, value } => send_cc(midi_out, dev, channel, control, value).await,
-        MidiMsg::PitchBend { bend } => pitch_bend(midi_out, dev, channel, bend).await,
-    }
+    let beats = unwrap_rw_lock(&bpq.1, 24.);
+    note::play(&midi_reqs, &pulse_watch, beats, req_body.into_inner()).await;
 
     HttpResponse::Ok()
 }
 
 #[post("/batch-midi")]
 async fn midi_pool_exec(
-    tempo: web::Data<Tempo>,
-    midi_out: web::Data<MidiOut>,
+    bpq: web::Data<(BPQMarker, BPQ)>,
+    midi_reqs: web::Data<MidiReqs>,
+    pulse_watch: web::Data<PulseWatch>,
     req_body: Json<Vec<MidiReqBody>>,
 ) -> HttpResponseBuilder {
-    join_all(req_body.clone().into_iter().map(async |msg| {
-        let tempo = tempo.clone();
-        let midi_out = midi_out.clone();
-
-        spawn_local(async move {
-            let dev = msg.midi_dev.clone();
-            let channel = msg.channel;
-
-            match msg.msg {
-                MidiMsg::PlayNote {
-                    note,
-                    velocity,
-                    duration,
-                } => {
-                    debug!("note: {note} => {dev}");
-
-                    if let Ok(tempo) = tempo.read() {
-                        play_note(
-                            *tempo,
-                            midi_out.clone(),
-                            dev,
-                            channel,
-                            note,
-                            velocity,
-                            duration,
-                        )
-                        .await;
-                    }
-                }
-                MidiMsg::StopNote { note } => stop_note(midi_out.clone(), dev, channel, note).await,
-                MidiMsg::CC { control, value } => {
-                    send_cc(midi_out.clone(), dev, channel, control, value).await
-                }
-                MidiMsg::PitchBend { bend } => {
-                    pitch_bend(midi_out.clone(), dev, channel, bend).await
-                }
-            }
-        })
-    }))
+    let beats = unwrap_rw_lock(&bpq.1, 24.);
+
+    join_all(
+        req_body
+            .into_inner()
+            .into_iter()
+            .map(|req| note::play(&midi_reqs, &pulse_watch, beats, req)),
+    )
     .await;
 
     HttpResponse::Ok()
 }
 
 #[post("/rest")]
-async fn rest(tempo: web::Data<Tempo>, durration: Json<NoteDuration>) -> HttpResponseBuilder {
-    // let tempo = tempo.read().await;
-    if let Ok(tempo) = tempo.read() {
-        // serde_json::to_string(&*tempo).map(|tempo| HttpResponse::Ok().body(tempo))
-        note::rest(*tempo, *durration).await;
-    }
+async fn rest(
+    bpq: web::Data<(BPQMarker, BPQ)>,
+    pulse_watch: web::Data<PulseWatch>,
+    durration: Json<NoteDuration>,
+) -> HttpResponseBuilder {
+    note::rest(&pulse_watch, unwrap_rw_lock(&bpq.1, 24.), *durration).await;
 
     HttpResponse::Ok()
 }
@@ -169,9 +169,7 @@ async fn get_bpq(bpq: web::Data<(BPQMarker, BPQ)>) -> HttpResponse {
 }
 
 #[get("/midi")]
-async fn get_devs(
-    virtual_devs: web::Data<Mutex<FxHashSet<String>>>,
-) -> Result<HttpResponse, serde_json::Error> {
+async fn get_devs(virtual_devs: web::Data<VirtualDevs>) -> Result<HttpResponse, serde_json::Error> {
     let midi_out = MidiOutput::new("MIDI-DAW-API").unwrap();
     let mut midi_devs_names: Vec<String> = midi_out
         .ports()
@@ -179,19 +177,81 @@ async fn get_devs(
         .filter_map(|port| midi_out.port_name(&port).ok().map(fmt_dev_name))
         // .map(|port| port.id())
         .collect();
-    midi_devs_names.append(&mut virtual_devs.lock().await.clone().into_iter().collect());
+    if let Ok(virtual_devs) = virtual_devs.read() {
+        midi_devs_names.extend(virtual_devs.iter().cloned());
+    }
 
     info!("{midi_devs_names:?}");
 
     serde_json::to_string(&midi_devs_names).map(|tempo| HttpResponse::Ok().body(tempo))
 }
 
+#[get("/midi/inputs")]
+async fn get_midi_inputs() -> Result<HttpResponse, serde_json::Error> {
+    let midi_in = MidiInput::new("MIDI-DAW-API").unwrap();
+    let midi_in_names: Vec<String> = midi_in
+        .ports()
+        .into_iter()
+        .filter_map(|port| midi_in.port_name(&port).ok().map(fmt_dev_name))
+        .filter(|dev_name| !is_own_port(dev_name))
+        .collect();
+
+    serde_json::to_string(&midi_in_names).map(|names| HttpResponse::Ok().body(names))
+}
+
+#[post("/midi/route")]
+async fn add_route(routes: web::Data<Routes>, route: Json<Route>) -> HttpResponseBuilder {
+    match routes.write() {
+        Ok(mut routes) => {
+            if !routes.contains(&route.0) {
+                info!(
+                    "routing midi from \"{}\" to \"{}\"",
+                    route.from_dev, route.to_dev
+                );
+                routes.push(route.0);
+            }
+
+            HttpResponse::Ok()
+        }
+        Err(e) => {
+            error!("{e}");
+            HttpResponse::InternalServerError()
+        }
+    }
+}
+
+#[post("/midi/route/rm")]
+async fn rm_route(routes: web::Data<Routes>, route: Json<Route>) -> HttpResponseBuilder {
+    match routes.write() {
+        Ok(mut routes) => {
+            routes.retain(|r| r != &route.0);
+
+            HttpResponse::Ok()
+        }
+        Err(e) => {
+            error!("{e}");
+            HttpResponse::InternalServerError()
+        }
+    }
+}
+
+#[get("/midi/route")]
+async fn get_routes(routes: web::Data<Routes>) -> HttpResponse {
+    match routes.read() {
+        Ok(routes) => HttpResponse::Ok().json(routes.clone()),
+        Err(e) => {
+            error!("{e}");
+            HttpResponse::InternalServerError().body(e.to_string())
+        }
+    }
+}
+
 // make an end point to make new virtual midi-out
 #[post("/new-dev")]
 async fn new_dev(
     req_body: Json<String>,
     new_dev_tx: web::Data<Sender<MidiDev>>,
-    virtual_devs: web::Data<Mutex<FxHashSet<String>>>,
+    virtual_devs: web::Data<VirtualDevs>,
 ) -> Result<HttpResponse, serde_json::Error> {
     let port_name = &req_body.0;
 
@@ -204,38 +264,24 @@ async fn new_dev(
     if let Err(e) = new_dev_tx.send(MidiDev::CreateVirtual(port_name.to_string())) {
         error!("{e}");
     }
-    virtual_devs.lock().await.insert(port_name.to_string());
+    if let Ok(mut virtual_devs) = virtual_devs.write() {
+        virtual_devs.insert(port_name.to_string());
+    }
 
     serde_json::to_string(&port_name).map(|tempo| HttpResponse::Ok().body(tempo))
 }
 
-#[post("/sequence/new")]
-async fn new_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
-    seq_name: Json<String>,
+#[post("/midi/clock-out")]
+async fn set_clock_out(
+    req_body: Json<MidiClockOutBody>,
+    new_dev_tx: web::Data<Sender<MidiDev>>,
 ) -> HttpResponseBuilder {
-    let msg = SequencerControlCmd::NewSequence {
-        name: Some(seq_name.0),
-        midi_dev: None,
-        channel: None,
+    let msg = MidiDev::SetClockOut {
+        dev_name: req_body.midi_dev.clone(),
+        on: req_body.on,
     };
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
-    }
-}
 
-#[post("/sequence/rm")]
-async fn rm_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
-    seq_name: Json<String>,
-) -> HttpResponseBuilder {
-    let msg = SequencerControlCmd::RmSequence { name: seq_name.0 };
-
-    match seq_coms.send(msg) {
+    match new_dev_tx.send(msg) {
         Ok(_) => HttpResponse::Ok(),
         Err(e) => {
             error!("{e}");
@@ -244,19 +290,24 @@ async fn rm_sequence(
     }
 }
 
-async fn do_get_sequences(seq_coms: web::Data<Sender<SequencerControlCmd>>) -> HttpResponse {
-    let (responder, mut recv_er) = oneshot::channel();
+#[get("/midi/clock-out")]
+async fn get_clock_out(new_dev_tx: web::Data<Sender<MidiDev>>) -> HttpResponse {
+    let (responder, recv_er) = oneshot::channel();
+
+    let msg = MidiDev::ListClockOut { responder };
 
-    let msg = SequencerControlCmd::GetSequences { responder };
+    match new_dev_tx.send(msg) {
+        Ok(_) => match recv_er.await {
+            Ok(res) => HttpResponse::Ok().json(res),
+            Err(e) => {
+                let error_msg = format!("reading reponse from midi out failed with error, {e}");
 
-    match seq_coms.send(msg) {
-        Ok(_) => loop {
-            if let Ok(res) = recv_er.try_recv() {
-                return HttpResponse::Ok().json(res);
+                error!("{error_msg}");
+                HttpResponse::InternalServerError().body(error_msg)
             }
         },
         Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
+            let error_msg = format!("sending control message to midi out failed with error, {e}");
 
             error!("{error_msg}");
             HttpResponse::InternalServerError().body(error_msg)
@@ -264,203 +315,329 @@ async fn do_get_sequences(seq_coms: web::Data<Sender<SequencerControlCmd>>) -> H
     }
 }
 
+#[post("/clock-source")]
+async fn set_clock_source(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    source: Json<ClockSource>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::SetClockSource(source.0);
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/launch-quant")]
+async fn set_launch_quant(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    quant: Json<LaunchQuant>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::SetLaunchQuant(quant.0);
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[get("/launch-quant")]
+async fn get_launch_quant(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    let res = ask(&seq_coms, |responder| {
+        (SequencerControlCmd::GetLaunchQuant { responder }, None)
+    })
+    .await;
+
+    match res {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(res) => res,
+    }
+}
+
+#[get("/clock-source")]
+async fn get_clock_source(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    let res = ask(&seq_coms, |responder| {
+        (SequencerControlCmd::GetClockSource { responder }, None)
+    })
+    .await;
+
+    match res {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(res) => res,
+    }
+}
+
+#[post("/sequence/new")]
+async fn new_sequence(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    seq_name: Json<String>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::NewSequence {
+        name: Some(seq_name.0),
+        midi_dev: None,
+        channel: None,
+    };
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/sequence/rm")]
+async fn rm_sequence(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    seq_name: Json<String>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::RmSequence { name: seq_name.0 };
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+async fn do_get_sequences(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    let res = ask(&seq_coms, |responder| {
+        (SequencerControlCmd::GetSequences { responder }, None)
+    })
+    .await;
+
+    match res {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(res) => res,
+    }
+}
+
 #[get("/sequence/names")]
-async fn get_sequences(seq_coms: web::Data<Sender<SequencerControlCmd>>) -> HttpResponse {
+async fn get_sequences(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
     do_get_sequences(seq_coms).await
 }
 
 #[get("/sequence")]
 async fn get_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     seq_name: web::Query<GetSequenceQuery>,
 ) -> HttpResponse {
-    let (responder, mut recv_er) = oneshot::channel();
-
-    let msg = SequencerControlCmd::GetSequence {
-        sequence: seq_name.sequence.clone(),
-        responder,
-    };
+    let sequence = seq_name.sequence.clone();
+    let res = ask(&seq_coms, |responder| {
+        let msg = SequencerControlCmd::GetSequence {
+            sequence: sequence.clone(),
+            responder,
+        };
+
+        (msg, None)
+    })
+    .await;
 
-    match seq_coms.send(msg) {
-        Ok(_) => match {
-            loop {
-                if let Ok(res) = recv_er.try_recv() {
-                    break res;
-                }
-            }
-        } {
-            Some(sequences) => HttpResponse::Ok().json(sequences),
-            None => {
-                let error_msg = format!("reading reponse from sequencer failed");
+    match res {
+        Ok(Some(sequence)) => HttpResponse::Ok().json(sequence),
+        Ok(None) => error_response(SequencerError::UnknownSequence { name: sequence }),
+        Err(res) => res,
+    }
+}
 
-                error!("{error_msg}");
-                HttpResponse::InternalServerError().body(error_msg)
-            }
-        },
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
+#[post("/undo")]
+async fn undo(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    sequencer_cmd(&seq_coms, SequencerControlCmd::Undo).await
+}
 
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
-    }
+#[post("/redo")]
+async fn redo(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    sequencer_cmd(&seq_coms, SequencerControlCmd::Redo).await
 }
 
 #[post("/sequence/play-one")]
 async fn play_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     seq_name: Json<String>,
-) -> HttpResponseBuilder {
-    let msg = SequencerControlCmd::Play(vec![seq_name.0.clone()]);
+) -> HttpResponse {
+    let msg = SequencerControlCmd::Play(vec![seq_name.0.clone()], None);
 
-    match seq_coms.send(msg) {
-        Ok(_) => {
-            info!("now playing: {}.", seq_name.0);
-            HttpResponse::Ok()
-        }
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
+    let res = sequencer_cmd(&seq_coms, msg).await;
+
+    if res.status().is_success() {
+        info!("now playing: {}.", seq_name.0);
     }
+
+    res
 }
 
 #[post("/sequence/play-these")]
 async fn play_these_sequences(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     seq_name: Json<Vec<String>>,
-) -> HttpResponseBuilder {
-    let msg = SequencerControlCmd::Play(seq_name.0.clone());
+) -> HttpResponse {
+    let msg = SequencerControlCmd::Play(seq_name.0.clone(), None);
 
-    match seq_coms.send(msg) {
-        Ok(_) => {
-            info!("playing the folowing sequences: {:?}", seq_name.0);
-            HttpResponse::Ok()
-        }
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
+    let res = sequencer_cmd(&seq_coms, msg).await;
+
+    if res.status().is_success() {
+        info!("playing the folowing sequences: {:?}", seq_name.0);
     }
+
+    res
+}
+
+#[post("/sequence/launch")]
+async fn launch_sequences(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<LaunchBody>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::Play(args.sequences.clone(), args.quant);
+
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 #[post("/sequence/play-all")]
-async fn play_all_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
-) -> HttpResponseBuilder {
+async fn play_all_sequence(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
     let msg = SequencerControlCmd::PlayAll;
 
-    match seq_coms.send(msg) {
-        Ok(_) => {
-            info!("playing every sequence.");
-            HttpResponse::Ok()
-        }
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
+    let res = sequencer_cmd(&seq_coms, msg).await;
+
+    if res.status().is_success() {
+        info!("playing every sequence.");
     }
+
+    res
 }
 
 #[post("/sequence/pause")]
 async fn pause_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     seq_name: Json<Vec<String>>,
-) -> HttpResponseBuilder {
+) -> HttpResponse {
     let msg = SequencerControlCmd::Pause(seq_name.0);
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 #[post("/sequence/pause-all")]
-async fn pause_all_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
-) -> HttpResponseBuilder {
+async fn pause_all_sequence(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
     let msg = SequencerControlCmd::PauseAll;
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 #[post("/sequence/stop-one")]
 async fn stop_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     seq_name: Json<String>,
-) -> HttpResponseBuilder {
+) -> HttpResponse {
     let msg = SequencerControlCmd::Stop(vec![seq_name.0]);
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 #[post("/sequence/stop-these")]
 async fn stop_some_sequences(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     seq_name: Json<Vec<String>>,
-) -> HttpResponseBuilder {
+) -> HttpResponse {
     let msg = SequencerControlCmd::Stop(seq_name.0);
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 #[post("/sequence/stop-all")]
-async fn stop_all_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
-) -> HttpResponseBuilder {
+async fn stop_all_sequence(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
     let msg = SequencerControlCmd::StopAll;
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/sequence/launch-stop")]
+async fn launch_stop_sequences(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<LaunchBody>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::QueueStop(args.sequences.clone(), args.quant);
+
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 #[post("/sequence/queue-stop")]
 async fn queue_stop_sequences(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     seq_name: Json<Vec<String>>,
-) -> HttpResponseBuilder {
-    let msg = SequencerControlCmd::QueueStop(seq_name.0);
+) -> HttpResponse {
+    let msg = SequencerControlCmd::QueueStop(seq_name.0, None);
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/scene")]
+async fn set_scene(seq_coms: web::Data<Sender<SequencerReq>>, scene: Json<Scene>) -> HttpResponse {
+    let msg = SequencerControlCmd::SetScene(scene.0);
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/scene/rm")]
+async fn rm_scene(seq_coms: web::Data<Sender<SequencerReq>>, name: Json<String>) -> HttpResponse {
+    let msg = SequencerControlCmd::RmScene { name: name.0 };
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[get("/scene")]
+async fn get_scenes(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    let res = ask(&seq_coms, |responder| {
+        (SequencerControlCmd::GetScenes { responder }, None)
+    })
+    .await;
+
+    match res {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(res) => res,
     }
 }
 
+#[post("/scene/launch")]
+async fn launch_scene(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<LaunchSceneBody>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::LaunchScene {
+        name: args.scene.clone(),
+        quant: args.quant,
+    };
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/arrangement")]
+async fn set_arrangement(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    arrangement: Json<Arrangement>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::SetArrangement(arrangement.0);
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[get("/arrangement")]
+async fn get_arrangement(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    let res = ask(&seq_coms, |responder| {
+        (SequencerControlCmd::GetArrangement { responder }, None)
+    })
+    .await;
+
+    match res {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(res) => res,
+    }
+}
+
+#[post("/arrangement/play")]
+async fn play_arrangement(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    from: Json<usize>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::PlayArrangement { from: from.0 };
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/arrangement/stop")]
+async fn stop_arrangement(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    let msg = SequencerControlCmd::StopArrangement;
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
 #[post("/sequence/add-note")]
 async fn add_note(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<AddNoteBody>,
-) -> HttpResponseBuilder {
+) -> HttpResponse {
     let msg = SequencerControlCmd::AddNote {
         sequence: args.sequence.clone(),
         step: args.step,
@@ -469,57 +646,81 @@ async fn add_note(
         note_len: args.note_len,
     };
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 #[post("/sequence/rm-note")]
 async fn rm_note(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<RmNoteBody>,
-) -> HttpResponseBuilder {
+) -> HttpResponse {
     let msg = SequencerControlCmd::RmNote {
         sequence: args.sequence.clone(),
         step: args.step,
         note: args.note,
     };
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/sequence/add-cmd")]
+async fn add_cmd(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<AddCmdBody>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::AddCmd {
+        sequence: args.sequence.clone(),
+        step: args.step,
+        cmd: args.cmd.clone(),
+    };
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/sequence/rm-cmd")]
+async fn rm_cmd(seq_coms: web::Data<Sender<SequencerReq>>, args: Json<RmCmdBody>) -> HttpResponse {
+    let msg = SequencerControlCmd::RmCmd {
+        sequence: args.sequence.clone(),
+        step: args.step,
+        cmd: args.cmd.clone(),
+    };
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/sequence/record")]
+async fn record(seq_coms: web::Data<Sender<SequencerReq>>, args: Json<RecordBody>) -> HttpResponse {
+    let msg = SequencerControlCmd::Record {
+        sequence: args.sequence.clone(),
+        midi_dev: args.midi_dev.clone(),
+        mode: args.mode,
+        count_in: args.count_in,
+    };
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/sequence/stop-recording")]
+async fn stop_recording(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    sequencer_cmd(&seq_coms, SequencerControlCmd::StopRecording).await
 }
 
 #[post("/sequence/set-dev")]
 async fn set_dev(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<SetDevBody>,
-) -> HttpResponseBuilder {
+) -> HttpResponse {
     let msg = SequencerControlCmd::SetSequenceDev {
         name: args.sequence.clone(),
         midi_dev: args.midi_dev.clone(),
     };
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok(),
-        Err(e) => {
-            error!("{e}");
-            HttpResponse::InternalServerError()
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 #[post("/sequence/rename")]
 async fn rename_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<RenameSequenceBody>,
 ) -> HttpResponse {
     let msg = SequencerControlCmd::RenameSequence {
@@ -527,20 +728,18 @@ async fn rename_sequence(
         new_name: args.new_name.clone(),
     };
 
-    match seq_coms.send(msg) {
-        Ok(_) => do_get_sequences(seq_coms).await,
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
+    let res = sequencer_cmd(&seq_coms, msg).await;
 
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
+    if !res.status().is_success() {
+        return res;
     }
+
+    do_get_sequences(seq_coms).await
 }
 
 #[post("/sequence/set-channel")]
 async fn set_channel(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<SetChannelBody>,
 ) -> HttpResponse {
     let msg = SequencerControlCmd::SetSequenceChannel {
@@ -548,20 +747,51 @@ async fn set_channel(
         channel: args.channel.clone(),
     };
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
+    sequencer_cmd(&seq_coms, msg).await
+}
 
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
-    }
+#[post("/sequence/set-step-len")]
+async fn set_step_len(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<SetStepLenBody>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::SetStepLen {
+        sequence: args.sequence.clone(),
+        step_len: args.step_len,
+    };
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/sequence/set-swing")]
+async fn set_swing(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<SetSwingBody>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::SetSwing {
+        sequence: args.sequence.clone(),
+        swing: args.swing,
+    };
+
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/sequence/set-groove")]
+async fn set_groove(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<SetGrooveBody>,
+) -> HttpResponse {
+    let msg = SequencerControlCmd::SetGroove {
+        sequence: args.sequence.clone(),
+        groove: args.groove.clone(),
+    };
+
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 #[post("/sequence/change-len-by")]
 async fn change_len_by(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<ChangeLenByBody>,
 ) -> HttpResponse {
     let msg = SequencerControlCmd::ChangeLenBy {
@@ -569,15 +799,7 @@ async fn change_len_by(
         amt: args.amt.clone(),
     };
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
-
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 // SaveSequence {
@@ -587,20 +809,12 @@ async fn change_len_by(
 
 #[post("/sequence/save-one")]
 async fn save_one_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<String>,
 ) -> HttpResponse {
     let msg = SequencerControlCmd::SaveSequence { sequence: args.0 };
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
-
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 // /// lists sequences that have been saved to disk that are not a part of a project
@@ -610,24 +824,16 @@ async fn save_one_sequence(
 // },
 
 #[get("/sequence/list-saved")]
-async fn get_saved_sequence(seq_coms: web::Data<Sender<SequencerControlCmd>>) -> HttpResponse {
-    let (responder, mut recv_er) = oneshot::channel();
-
-    let msg = SequencerControlCmd::ListSavedSequences { responder };
-
-    match seq_coms.send(msg) {
-        Ok(_) => loop {
-            if let Ok(res) = recv_er.try_recv() {
-                return HttpResponse::Ok().json(res);
-            }
-        },
-
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
+async fn get_saved_sequence(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    let res = ask(&seq_coms, |responder| {
+        (SequencerControlCmd::ListSavedSequences { responder }, None)
+    })
+    .await;
 
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
+    match res {
+        Ok(Ok(saved)) => HttpResponse::Ok().json(saved),
+        Ok(Err(e)) => error_response(e),
+        Err(res) => res,
     }
 }
 
@@ -639,20 +845,12 @@ async fn get_saved_sequence(seq_coms: web::Data<Sender<SequencerControlCmd>>) ->
 
 #[post("/sequence/load-one")]
 async fn load_one_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<String>,
 ) -> HttpResponse {
     let msg = SequencerControlCmd::LoadSequence { sequence: args.0 };
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
-
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 // ///
@@ -663,20 +861,12 @@ async fn load_one_sequence(
 
 #[post("/sequence/rm-one")]
 async fn rm_one_sequence(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<String>,
 ) -> HttpResponse {
-    let msg = SequencerControlCmd::RmSequence { name: args.0 };
-
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
+    let msg = SequencerControlCmd::RmSavedSequence { sequence: args.0 };
 
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 // /// saves all seqeunces into a sub folder of the data dir. the base file name will be based on the sequences name
@@ -686,21 +876,101 @@ async fn rm_one_sequence(
 
 #[post("/project/save")]
 async fn save_project(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<String>,
 ) -> HttpResponse {
     let msg = SequencerControlCmd::SaveProject {
         project_name: args.0,
     };
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
+    sequencer_cmd(&seq_coms, msg).await
+}
 
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
+/// responds with a standard midi file as a download. the file name is percent-encoded, so any
+/// sequence or project name makes a valid header.
+fn smf_response(name: &str, smf: Vec<u8>) -> HttpResponse {
+    let disposition = ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".into()),
+            language_tag: None,
+            value: format!("{name}.mid").into_bytes(),
+        })],
+    };
+
+    HttpResponse::Ok()
+        .content_type("audio/midi")
+        .insert_header(disposition)
+        .body(smf)
+}
+
+#[post("/sequence/export-smf")]
+async fn export_sequence_smf(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<String>,
+) -> HttpResponse {
+    let res = ask(&seq_coms, |responder| {
+        let msg = SequencerControlCmd::ExportSmf {
+            sequence: args.0.clone(),
+            responder,
+        };
+
+        (msg, None)
+    })
+    .await;
+
+    match res {
+        Ok(Ok(smf)) => smf_response(&args.0, smf),
+        Ok(Err(e)) => error_response(e),
+        Err(res) => res,
+    }
+}
+
+#[post("/sequence/import-smf")]
+async fn import_smf(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<ImportSmfBody>,
+) -> HttpResponse {
+    let sequences = match sequences_from_smf(&args.name, &args.smf) {
+        Ok(sequences) => sequences,
+        Err(message) => {
+            error!("{message}");
+            // the file came from the client, so a bad one is their error.
+            let body = ErrorBody::from(SequencerError::ParseError { message });
+
+            return HttpResponse::BadRequest().json(body);
         }
+    };
+    let names: Vec<String> = sequences.iter().map(|seq| seq.name.clone()).collect();
+
+    let res = sequencer_cmd(&seq_coms, SequencerControlCmd::ImportSequences(sequences)).await;
+
+    if !res.status().is_success() {
+        return res;
+    }
+
+    HttpResponse::Ok().json(names)
+}
+
+#[post("/project/export-smf")]
+async fn export_project_smf(
+    seq_coms: web::Data<Sender<SequencerReq>>,
+    args: Json<String>,
+) -> HttpResponse {
+    let res = ask(&seq_coms, |responder| {
+        let msg = SequencerControlCmd::ExportProjectSmf {
+            project_name: args.0.clone(),
+            responder,
+        };
+
+        (msg, None)
+    })
+    .await;
+
+    match res {
+        Ok(Ok(smf)) => smf_response(&args.0, smf),
+        Ok(Err(e)) => error_response(e),
+        Err(res) => res,
     }
 }
 
@@ -711,24 +981,16 @@ async fn save_project(
 // },
 
 #[get("/project/list-saved")]
-async fn get_saved_projects(seq_coms: web::Data<Sender<SequencerControlCmd>>) -> HttpResponse {
-    let (responder, mut recv_er) = oneshot::channel();
-
-    let msg = SequencerControlCmd::ListSavedProjects { responder };
-
-    match seq_coms.send(msg) {
-        Ok(_) => loop {
-            if let Ok(res) = recv_er.try_recv() {
-                return HttpResponse::Ok().json(res);
-            }
-        },
-
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
+async fn get_saved_projects(seq_coms: web::Data<Sender<SequencerReq>>) -> HttpResponse {
+    let res = ask(&seq_coms, |responder| {
+        (SequencerControlCmd::ListSavedProjects { responder }, None)
+    })
+    .await;
 
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
+    match res {
+        Ok(Ok(saved)) => HttpResponse::Ok().json(saved),
+        Ok(Err(e)) => error_response(e),
+        Err(res) => res,
     }
 }
 
@@ -740,22 +1002,14 @@ async fn get_saved_projects(seq_coms: web::Data<Sender<SequencerControlCmd>>) ->
 
 #[post("/project/load")]
 async fn load_project(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<String>,
 ) -> HttpResponse {
     let msg = SequencerControlCmd::LoadSavedProject {
         project_name: args.0,
     };
 
-    match seq_coms.send(msg) {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
-
-            error!("{error_msg}");
-            HttpResponse::InternalServerError().body(error_msg)
-        }
-    }
+    sequencer_cmd(&seq_coms, msg).await
 }
 
 // /// rm a project from storage.
@@ -766,17 +1020,104 @@ async fn load_project(
 
 #[post("/project/rm")]
 async fn rm_one_project(
-    seq_coms: web::Data<Sender<SequencerControlCmd>>,
+    seq_coms: web::Data<Sender<SequencerReq>>,
     args: Json<String>,
 ) -> HttpResponse {
     let msg = SequencerControlCmd::RmSavedProject {
         project_name: args.0,
     };
 
-    match seq_coms.send(msg) {
+    sequencer_cmd(&seq_coms, msg).await
+}
+
+#[post("/automation/start")]
+async fn start_automation(
+    automation_tx: web::Data<Sender<AutomationCmd>>,
+    args: Json<StartAutomationBody>,
+) -> HttpResponse {
+    // wave-tables are read from disk here, rather then on the midi out thread.
+    let (conf, res) = match web::block(move || {
+        let res = make_automation(&args.0);
+
+        (args.0, res)
+    })
+    .await
+    {
+        Ok(made) => made,
+        Err(e) => {
+            let error_msg = format!("making automation failed with error, {e}");
+
+            error!("{error_msg}");
+            return HttpResponse::InternalServerError().body(error_msg);
+        }
+    };
+    let automation = match res {
+        Ok(automation) => automation,
+        Err(e) => return error_response(e),
+    };
+
+    if let Err(e) = automation_tx.send(AutomationCmd::Start { conf, automation }) {
+        let error_msg = format!("sending control message to automations failed with error, {e}");
+
+        error!("{error_msg}");
+        return HttpResponse::InternalServerError().body(error_msg);
+    }
+
+    HttpResponse::Ok().finish()
+}
+
+#[post("/automation/stop")]
+async fn stop_automation(
+    automation_tx: web::Data<Sender<AutomationCmd>>,
+    args: Json<String>,
+) -> HttpResponse {
+    let msg = AutomationCmd::Stop(args.0);
+
+    match automation_tx.send(msg) {
         Ok(_) => HttpResponse::Ok().finish(),
         Err(e) => {
-            let error_msg = format!("sending control message to sequencer failed with error, {e}");
+            let error_msg =
+                format!("sending control message to automations failed with error, {e}");
+
+            error!("{error_msg}");
+            HttpResponse::InternalServerError().body(error_msg)
+        }
+    }
+}
+
+#[post("/automation/stop-all")]
+async fn stop_all_automations(automation_tx: web::Data<Sender<AutomationCmd>>) -> HttpResponse {
+    match automation_tx.send(AutomationCmd::StopAll) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            let error_msg =
+                format!("sending control message to automations failed with error, {e}");
+
+            error!("{error_msg}");
+            HttpResponse::InternalServerError().body(error_msg)
+        }
+    }
+}
+
+#[get("/automation")]
+async fn get_automations(automation_tx: web::Data<Sender<AutomationCmd>>) -> HttpResponse {
+    let (responder, recv_er) = oneshot::channel();
+
+    let msg = AutomationCmd::List { responder };
+
+    match automation_tx.send(msg) {
+        Ok(_) => match recv_er.await {
+            Ok(res) => HttpResponse::Ok().json(res),
+            Err(e) => {
+                let error_msg = format!("reading reponse from automations failed with error, {e}");
+
+                error!("{error_msg}");
+                HttpResponse::InternalServerError().body(error_msg)
+            }
+        },
+        Err(e) => {
+            let error_msg =
+                format!("sending control message to automations failed with error, {e}");
 
             error!("{error_msg}");
             HttpResponse::InternalServerError().body(error_msg)
@@ -856,21 +1197,31 @@ async fn rm_one_project(
 //     }
 // }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     tempo: Tempo,
     bpq: BPQ,
     midi_out: MidiOut,
+    midi_reqs: MidiReqs,
+    pulse_watch: PulseWatch,
     new_dev_tx: Sender<MidiDev>,
-    sequencer_tx: Sender<SequencerControlCmd>,
+    sequencer_tx: Sender<SequencerReq>,
+    automation_tx: Sender<AutomationCmd>,
+    routes: Routes,
+    virtual_devs: VirtualDevs,
     mb_server: MbServer,
     server_tx: MbServerHandle,
 ) -> std::io::Result<()> {
     let tempo = web::Data::new(tempo);
     // let bpq = web::Data::new(bpq);
     let midi_out = web::Data::new(midi_out);
+    let midi_reqs = web::Data::new(midi_reqs);
+    let pulse_watch = web::Data::new(pulse_watch);
     let new_dev_tx = web::Data::new(new_dev_tx);
     let seq_tx = web::Data::new(sequencer_tx);
-    let virtual_devs = web::Data::new(Mutex::new(FxHashSet::<String>::default()));
+    let automation_tx = web::Data::new(automation_tx);
+    let routes = web::Data::new(routes);
+    let virtual_devs = web::Data::new(virtual_devs);
     // let msg_event_addr = web::Data::new(MbMessageEvent.start());
 
     let _chat_server = spawn(mb_server.run());
@@ -888,6 +1239,8 @@ pub async fn run(
         .without_time()
         .init();
 
+    let auth = web::Data::new(Auth::load()?);
+
     // let _clock_notif_jh = std::thread::spawn({
     //     let tempo = tempo.clone();
     //     let server_tx = server_tx.clone();
@@ -907,23 +1260,39 @@ pub async fn run(
 
         move || {
             App::new()
-                .wrap(TracingLogger::default())
+                .wrap(from_fn(check_auth))
+                .wrap(TracingLogger::<NoQuerySpan>::new())
                 .app_data(tempo.clone())
                 .app_data(web::Data::new((BPQMarker, bpq.clone())))
                 .app_data(midi_out.clone())
+                .app_data(midi_reqs.clone())
+                .app_data(pulse_watch.clone())
                 .app_data(server_tx.clone())
                 .app_data(new_dev_tx.clone())
                 .app_data(virtual_devs.clone())
                 .app_data(seq_tx.clone())
+                .app_data(automation_tx.clone())
+                .app_data(routes.clone())
+                .app_data(auth.clone())
                 .service(midi)
                 .service(midi_pool_exec)
                 .service(get_devs)
+                .service(get_midi_inputs)
+                .service(add_route)
+                .service(rm_route)
+                .service(get_routes)
                 .service(get_tempo)
                 .service(set_tempo)
                 .service(get_bpq)
                 .service(set_bpq)
                 .service(rest)
                 .service(new_dev)
+                .service(set_clock_out)
+                .service(get_clock_out)
+                .service(set_clock_source)
+                .service(get_clock_source)
+                .service(set_launch_quant)
+                .service(get_launch_quant)
                 .service(new_sequence)
                 .service(rm_sequence)
                 .service(get_sequences)
@@ -931,26 +1300,52 @@ pub async fn run(
                 .service(play_sequence)
                 .service(play_these_sequences)
                 .service(play_all_sequence)
+                .service(undo)
+                .service(redo)
+                .service(launch_sequences)
                 .service(pause_sequence)
                 .service(pause_all_sequence)
                 .service(stop_sequence)
                 .service(stop_some_sequences)
                 .service(stop_all_sequence)
                 .service(queue_stop_sequences)
+                .service(launch_stop_sequences)
+                .service(set_scene)
+                .service(rm_scene)
+                .service(get_scenes)
+                .service(launch_scene)
+                .service(set_arrangement)
+                .service(get_arrangement)
+                .service(play_arrangement)
+                .service(stop_arrangement)
                 .service(add_note)
                 .service(rm_note)
+                .service(add_cmd)
+                .service(rm_cmd)
+                .service(record)
+                .service(stop_recording)
                 .service(set_dev)
                 .service(rename_sequence)
                 .service(set_channel)
                 .service(change_len_by)
+                .service(set_step_len)
+                .service(set_swing)
+                .service(set_groove)
                 .service(save_one_sequence)
                 .service(get_saved_sequence)
                 .service(load_one_sequence)
                 .service(rm_one_sequence)
                 .service(save_project)
+                .service(export_sequence_smf)
+                .service(import_smf)
+                .service(export_project_smf)
                 .service(get_saved_projects)
                 .service(load_project)
                 .service(rm_one_project)
+                .service(start_automation)
+                .service(stop_automation)
+                .service(stop_all_automations)
+                .service(get_automations)
                 .service(message_bus::message_bus)
         }
     })
@@ -1,96 +1,49 @@
-use crate::server::MidiOut;
-use actix::clock::sleep;
-use actix_web::web::{self};
-use midi_daw_types::{MidiChannel, NoteDuration};
-use midi_msg::ControlChange;
-use std::time::Duration;
+use crate::{
+    clock::note_pulses,
+    server::{MidiReqs, PulseWatch},
+};
+use midi_daw_types::{MidiMsg, MidiReqBody, NoteDuration};
 use tracing::log::*;
 
-pub async fn rest(tempo: f64, dur: NoteDuration) {
-    let (mul, denom) = match dur {
-        NoteDuration::Wn(n) => (n, 1.0),
-        NoteDuration::Hn(n) => (n, 2.0),
-        NoteDuration::Qn(n) => (n, 4.0),
-        NoteDuration::En(n) => (n, 8.0),
-        NoteDuration::Sn(n) => (n, 16.0),
-        NoteDuration::Tn(n) => (n, 32.0),
-        NoteDuration::S4n(n) => (n, 64.0),
-    };
-    let mul = mul as f64;
-
-    sleep(Duration::from_secs_f64(
-        ((60.0 / tempo) * 2.0 / denom) * mul,
-    ))
-    .await;
+/// waits until pulse has happened on the pulse clock.
+async fn wait_for_pulse(pulse_watch: &PulseWatch, pulse: u64) {
+    // only fails if midi out stopped, in which case there is nothing left to wait for.
+    let _ = pulse_watch.clone().wait_for(|now| *now >= pulse).await;
 }
 
-pub async fn play_note(
-    tempo: f64,
-    midi_out: web::Data<MidiOut>,
-    dev: String,
-    channel: MidiChannel,
-    note: u8,
-    velocity: u8,
-    dur: NoteDuration,
-) {
-    debug!("playing note {note} on {dev} with velocity {velocity}");
-
-    let msg = midi_msg::MidiMsg::ChannelVoice {
-        channel: channel.into(),
-        msg: midi_msg::ChannelVoiceMsg::NoteOn { note, velocity },
-    };
-
-    _ = midi_out.send((dev.clone(), msg));
-
-    rest(tempo, dur).await;
-
-    let msg = midi_msg::MidiMsg::ChannelVoice {
-        channel: channel.into(),
-        msg: midi_msg::ChannelVoiceMsg::NoteOff { note, velocity },
-    };
+/// waits for the length of dur, measured on the pulse clock.
+pub async fn rest(pulse_watch: &PulseWatch, bpq: f64, dur: NoteDuration) {
+    let start = *pulse_watch.borrow();
 
-    _ = midi_out.send((dev, msg));
+    wait_for_pulse(pulse_watch, start + note_pulses(dur, bpq)).await;
 }
 
-pub async fn stop_note(midi_out: web::Data<MidiOut>, dev: String, channel: MidiChannel, note: u8) {
-    let msg = midi_msg::MidiMsg::ChannelVoice {
-        channel: channel.into(),
-        msg: midi_msg::ChannelVoiceMsg::NoteOff {
+/// sends req to midi out. notes are ended by midi out on the pulse clock, this returns once they
+/// have been.
+pub async fn play(midi_reqs: &MidiReqs, pulse_watch: &PulseWatch, bpq: f64, req: MidiReqBody) {
+    let start = *pulse_watch.borrow();
+    let len = match req.msg {
+        MidiMsg::PlayNote {
             note,
-            velocity: 127,
-        },
+            velocity,
+            duration,
+        } => {
+            debug!(
+                "playing note {note} on {} with velocity {velocity}",
+                req.midi_dev
+            );
+
+            Some(note_pulses(duration, bpq))
+        }
+        _ => None,
     };
 
-    _ = midi_out.send((dev, msg));
-}
-
-pub async fn send_cc(
-    midi_out: web::Data<MidiOut>,
-    dev: String,
-    channel: MidiChannel,
-    cc: u8,
-    value: u8,
-) {
-    let msg = midi_msg::MidiMsg::ChannelVoice {
-        channel: channel.into(),
-        msg: midi_msg::ChannelVoiceMsg::ControlChange {
-            control: ControlChange::CC { control: cc, value },
-        },
-    };
-
-    _ = midi_out.send((dev, msg));
-}
-
-pub async fn pitch_bend(
-    midi_out: web::Data<MidiOut>,
-    dev: String,
-    channel: MidiChannel,
-    bend: u16,
-) {
-    let msg = midi_msg::MidiMsg::ChannelVoice {
-        channel: channel.into(),
-        msg: midi_msg::ChannelVoiceMsg::PitchBend { bend },
-    };
+    if let Err(e) = midi_reqs.send(req) {
+        error!("sending midi to midi out failed with error: {e}");
+        return;
+    }
 
-    _ = midi_out.send((dev, msg));
+    if let Some(len) = len {
+        wait_for_pulse(pulse_watch, start + len).await;
+    }
 }
@@ -0,0 +1,303 @@
+use super::*;
+use crate::midi::automation::AutomationEngine;
+use crate::sequencer::CONTROL_QUEUE_LEN;
+use actix_web::test::read_body_json;
+use actix_web::{
+    http::StatusCode,
+    test::{TestRequest, call_service, init_service},
+};
+use crossbeam::channel::{Receiver, bounded, unbounded};
+use futures::{future::join, poll};
+use midi_daw_types::{
+    MidiChannel, MidiMsg,
+    automation::{AutomationConf, AutomationTarget, SyncTime, lfo::LfoConfig},
+};
+use std::{
+    pin::pin,
+    sync::{Arc, RwLock},
+    thread,
+    time::Duration,
+};
+use tokio::time::timeout;
+
+/// how many sequence listings are left waiting on the sequencer while a midi message is sent.
+const PENDING_GETS: usize = 64;
+
+fn bpq_data(bpq: f64) -> web::Data<(BPQMarker, BPQ)> {
+    web::Data::new((BPQMarker, Arc::new(RwLock::new(bpq))))
+}
+
+/// a stand in for the sequencer that sits on every GetSequences request until it's released.
+fn stalled_sequencer(controls: Receiver<SequencerReq>, release: Receiver<()>) {
+    let mut held = Vec::new();
+
+    while held.len() < PENDING_GETS {
+        match controls.recv() {
+            Ok((SequencerControlCmd::GetSequences { responder }, _)) => held.push(responder),
+            Ok(_) => {}
+            Err(_) => return,
+        }
+    }
+
+    let _ = release.recv();
+
+    for responder in held {
+        let _ = responder.send(vec!["seq-1".into()]);
+    }
+}
+
+#[actix_web::test]
+async fn pending_sequencer_requests_dont_block_midi() {
+    let (seq_tx, seq_rx) = bounded(CONTROL_QUEUE_LEN);
+    let (midi_tx, midi_rx) = unbounded::<MidiReqBody>();
+    let (release_tx, release_rx) = unbounded();
+    let sequencer = thread::spawn(move || stalled_sequencer(seq_rx, release_rx));
+
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(seq_tx))
+            .app_data(web::Data::new(midi_tx))
+            .app_data(web::Data::new(watch::channel(0u64).1))
+            .app_data(bpq_data(24.0))
+            .service(get_sequences)
+            .service(midi),
+    )
+    .await;
+
+    let gets = join_all(
+        (0..PENDING_GETS)
+            .map(|_| call_service(&app, TestRequest::get().uri("/sequence/names").to_request())),
+    );
+
+    let send_midi = async {
+        let body = MidiReqBody::new(
+            "test-dev".into(),
+            MidiChannel::Ch1,
+            MidiMsg::CC {
+                control: 7,
+                value: 100,
+            },
+        );
+        let res = call_service(
+            &app,
+            TestRequest::post().uri("/midi").set_json(body).to_request(),
+        )
+        .await;
+
+        // the midi message has to get through while every listing is still waiting.
+        let sent = midi_rx.try_recv();
+        let _ = release_tx.send(());
+
+        (res.status(), sent)
+    };
+
+    let (gets, (midi_status, sent)) = join(gets, send_midi).await;
+
+    assert_eq!(midi_status, StatusCode::OK);
+    assert_eq!(
+        sent.map(|req| req.midi_dev).ok(),
+        Some("test-dev".to_string())
+    );
+    assert!(gets.iter().all(|res| res.status() == StatusCode::OK));
+    sequencer.join().unwrap();
+}
+
+#[actix_web::test]
+async fn full_control_queue_is_unavailable() {
+    let (seq_tx, _seq_rx) = bounded::<SequencerReq>(1);
+    let (responder, _recv_er) = oneshot::channel();
+    seq_tx
+        .send((SequencerControlCmd::GetLaunchQuant { responder }, None))
+        .unwrap();
+
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(seq_tx))
+            .service(get_sequences),
+    )
+    .await;
+    let res = call_service(&app, TestRequest::get().uri("/sequence/names").to_request()).await;
+
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(res.headers().contains_key("Retry-After"));
+}
+
+#[actix_web::test]
+async fn queued_requests_that_time_out_arent_retried() {
+    let (seq_tx, seq_rx) = bounded::<SequencerReq>(CONTROL_QUEUE_LEN);
+    // takes the request but never replies to it.
+    let sequencer = thread::spawn(move || seq_rx.recv());
+
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(seq_tx))
+            .service(get_sequences),
+    )
+    .await;
+    let res = call_service(&app, TestRequest::get().uri("/sequence/names").to_request()).await;
+
+    assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+    assert!(!res.headers().contains_key("Retry-After"));
+    drop(sequencer.join().unwrap());
+}
+
+#[actix_web::test]
+async fn dropped_responder_is_an_error() {
+    let (seq_tx, seq_rx) = bounded::<SequencerReq>(CONTROL_QUEUE_LEN);
+    let sequencer = thread::spawn(move || drop(seq_rx.recv()));
+
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(seq_tx))
+            .service(get_sequences),
+    )
+    .await;
+    let res = call_service(&app, TestRequest::get().uri("/sequence/names").to_request()).await;
+
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    sequencer.join().unwrap();
+}
+
+#[actix_web::test]
+async fn rests_last_for_their_pulses() {
+    let (pulse_tx, pulse_rx) = watch::channel(0u64);
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(pulse_rx))
+            .app_data(bpq_data(24.0))
+            .service(rest),
+    )
+    .await;
+    let req = TestRequest::post()
+        .uri("/rest")
+        .set_json(NoteDuration::En(1))
+        .to_request();
+    let mut res = pin!(call_service(&app, req));
+
+    // an eighth note is 12 pulses at 24 BPQ.
+    for pulse in 1..=12 {
+        assert!(
+            poll!(res.as_mut()).is_pending(),
+            "the rest ended after {} pulses",
+            pulse - 1
+        );
+        pulse_tx.send_replace(pulse);
+    }
+
+    let res = timeout(Duration::from_secs(1), res).await;
+
+    assert_eq!(res.map(|res| res.status()).ok(), Some(StatusCode::OK));
+}
+
+#[actix_web::test]
+async fn invalid_automations_are_bad_requests() {
+    let (automation_tx, automation_rx) = unbounded();
+    let automations = thread::spawn(move || {
+        let mut engine = AutomationEngine::default();
+
+        while let Ok(cmd) = automation_rx.recv() {
+            engine.handle_cmd(cmd);
+        }
+    });
+
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(automation_tx))
+            .service(start_automation),
+    )
+    .await;
+    let body = StartAutomationBody::new(
+        "wobble".into(),
+        AutomationConf::Lfo(LfoConfig::WaveTable {
+            file: "/no/such/wave-table.wav".into(),
+            freq: SyncTime::Hz(1.0),
+        }),
+        "test-dev".into(),
+        MidiChannel::Ch1,
+        AutomationTarget::CC {
+            control: 1,
+            min: 0,
+            max: 127,
+        },
+    );
+    let req = TestRequest::post()
+        .uri("/automation/start")
+        .set_json(body)
+        .to_request();
+    let res = call_service(&app, req).await;
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let body: ErrorBody = read_body_json(res).await;
+
+    assert!(matches!(
+        body.error,
+        SequencerError::InvalidAutomation { name, .. } if name == "wobble"
+    ));
+
+    drop(app);
+    automations.join().unwrap();
+}
+
+#[actix_web::test]
+async fn bad_midi_files_are_bad_requests() {
+    let (seq_tx, _seq_rx) = bounded::<SequencerReq>(CONTROL_QUEUE_LEN);
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(seq_tx))
+            .service(import_smf),
+    )
+    .await;
+    let body = ImportSmfBody::new("song".into(), b"not a midi file".to_vec());
+    let req = TestRequest::post()
+        .uri("/sequence/import-smf")
+        .set_json(body)
+        .to_request();
+    let res = call_service(&app, req).await;
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let body: ErrorBody = read_body_json(res).await;
+
+    assert!(matches!(body.error, SequencerError::ParseError { .. }));
+}
+
+#[actix_web::test]
+async fn rm_one_removes_the_saved_sequence() {
+    let (seq_tx, seq_rx) = bounded::<SequencerReq>(CONTROL_QUEUE_LEN);
+    let sequencer = thread::spawn(move || {
+        let (cmd, responder) = seq_rx.recv().unwrap();
+        let _ = responder.unwrap().send(Ok(()));
+
+        cmd
+    });
+
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(seq_tx))
+            .service(rm_one_sequence),
+    )
+    .await;
+    let req = TestRequest::post()
+        .uri("/sequence/rm-one")
+        .set_json("seq-1")
+        .to_request();
+    let res = call_service(&app, req).await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(matches!(
+        sequencer.join().unwrap(),
+        SequencerControlCmd::RmSavedSequence { sequence } if sequence == "seq-1"
+    ));
+}
+
+#[test]
+fn smf_downloads_escape_their_names() {
+    let res = smf_response("a \"b\"; c\r\nd", Vec::new());
+    let disposition = res.headers().get("Content-Disposition").unwrap();
+
+    assert_eq!(
+        disposition.to_str().unwrap(),
+        "attachment; filename*=UTF-8''a%20%22b%22%3B%20c%0D%0Ad.mid"
+    );
+}
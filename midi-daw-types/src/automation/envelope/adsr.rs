@@ -0,0 +1,139 @@
+use crate::automation::{
+    AutomationTrait, DEFAULT_TEMPO, HIFI_UPDATE_RATE, PULSE_UPDATE_RATE, SyncTime,
+};
+#[cfg(feature = "pyo3")]
+use pyo3::prelude::*;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+enum Stage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// an ADSR envelope generator. AR envelopes are ADSRs with no decay & a full sustain.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(PartialEq, PartialOrd, Clone, Debug)]
+pub struct Adsr {
+    atk: SyncTime,
+    dcy: SyncTime,
+    sus: f64,
+    rel: SyncTime,
+    is_ar: bool,
+    update_rate: f64,
+    tempo: f64,
+    stage: Stage,
+    /// the level the release stage started from
+    rel_from: f64,
+    last_value: f64,
+}
+
+impl Adsr {
+    pub fn new(atk: SyncTime, dcy: SyncTime, sus: f64, rel: SyncTime, hifi: bool) -> Self {
+        Self {
+            atk,
+            dcy,
+            sus: sus.clamp(0.0, 1.0),
+            rel,
+            is_ar: false,
+            update_rate: if hifi {
+                HIFI_UPDATE_RATE
+            } else {
+                PULSE_UPDATE_RATE
+            },
+            tempo: DEFAULT_TEMPO,
+            stage: Stage::Idle,
+            rel_from: 0.0,
+            last_value: 0.0,
+        }
+    }
+
+    pub fn new_ar(atk: SyncTime, rel: SyncTime, hifi: bool) -> Self {
+        let mut env = Self::new(atk, SyncTime::Secs(0.0), 1.0, rel, hifi);
+        env.is_ar = true;
+
+        env
+    }
+
+    /// the amount to move per update to cover 0.0 to 1.0 in the length of time.
+    fn rate(&self, time: SyncTime) -> f64 {
+        let secs = time.secs(self.tempo);
+
+        if secs > 0.0 {
+            1.0 / (secs * self.update_rate)
+        } else {
+            1.0
+        }
+    }
+}
+
+impl AutomationTrait for Adsr {
+    fn sub_type(&self) -> String {
+        if self.is_ar { "ar" } else { "adsr" }.into()
+    }
+
+    fn update(&mut self) {
+        match self.stage {
+            Stage::Idle => self.last_value = 0.0,
+            Stage::Attack => {
+                self.last_value += self.rate(self.atk);
+
+                if self.last_value >= 1.0 {
+                    self.last_value = 1.0;
+                    self.stage = Stage::Decay;
+                }
+            }
+            Stage::Decay => {
+                self.last_value -= self.rate(self.dcy) * (1.0 - self.sus);
+
+                if self.last_value <= self.sus {
+                    self.last_value = self.sus;
+                    self.stage = Stage::Sustain;
+                }
+            }
+            Stage::Sustain => self.last_value = self.sus,
+            Stage::Release => {
+                self.last_value -= self.rate(self.rel) * self.rel_from;
+
+                if self.last_value <= 0.0 {
+                    self.last_value = 0.0;
+                    self.stage = Stage::Idle;
+                }
+            }
+        }
+    }
+
+    fn get_value(&self) -> f64 {
+        self.last_value
+    }
+
+    fn set_update_rate(&mut self, update_rate: f64) {
+        if update_rate > 0.0 {
+            self.update_rate = update_rate;
+        }
+    }
+
+    fn is_bipolar(&self) -> bool {
+        false
+    }
+
+    fn set_tempo(&mut self, tempo: f64) {
+        if tempo > 0.0 {
+            self.tempo = tempo;
+        }
+    }
+
+    /// (re)starts the attack from the current level.
+    fn gate_on(&mut self) {
+        self.stage = Stage::Attack;
+    }
+
+    fn gate_off(&mut self) {
+        if self.stage != Stage::Idle {
+            self.rel_from = self.last_value;
+            self.stage = Stage::Release;
+        }
+    }
+}
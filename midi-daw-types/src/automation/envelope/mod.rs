@@ -1,25 +1,34 @@
 use enum_dispatch::enum_dispatch;
+#[cfg(feature = "pyo3")]
 use pyo3::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::automation::SyncTime;
+
 pub mod adsr;
 
-#[pyclass]
+/// sustain is a level between 0.0 & 1.0.
+#[cfg_attr(feature = "pyo3", pyclass)]
 #[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Debug)]
 pub enum EnvConfig {
     /// Attack Decay Sustain Release.
     ADSR {
-        atk: f64,
-        dcy: f64,
+        atk: SyncTime,
+        dcy: SyncTime,
         sus: f64,
-        rel: f64,
+        rel: SyncTime,
+        hifi: bool,
     },
     /// Attack Release
-    AR { atk: f64, rel: f64 },
+    AR {
+        atk: SyncTime,
+        rel: SyncTime,
+        hifi: bool,
+    },
 }
 
-#[pyclass]
-#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Debug)]
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(PartialEq, PartialOrd, Clone, Debug)]
 #[enum_dispatch(AutomationTrait)]
 pub enum Envelope {
     Adsr(adsr::Adsr),
@@ -4,6 +4,12 @@ use enum_dispatch::enum_dispatch;
 use pyo3::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::automation::{
+    SyncTime,
+    lfo::shapes::{Phasor, Shape, ShapeLfo},
+};
+
+pub mod shapes;
 pub mod wavetable;
 
 // #[pyclass]
@@ -11,56 +17,110 @@ pub mod wavetable;
 #[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Debug)]
 pub enum LfoConfig {
     /// wave-table lfo
-    WaveTable { file: String, freq: f64 },
-    // /// sin wave
-    // Sin {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// triangle wave
-    // Triangle {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// saw wave going up
-    // SawUp {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// saw wave going down
-    // SawDown {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// anti-log Triangle Wave
-    // AntiLog {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// anti-log saw wave going up
-    // AntiLogUp {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// anti-log saw wave going down
-    // AntiLogDown {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
+    WaveTable { file: String, freq: SyncTime },
+    /// sin wave
+    Sin {
+        freq: SyncTime,
+        one_shot: bool,
+        bipolar: bool,
+        hifi: bool,
+    },
+    /// triangle wave
+    Triangle {
+        freq: SyncTime,
+        one_shot: bool,
+        bipolar: bool,
+        hifi: bool,
+    },
+    /// saw wave going up
+    SawUp {
+        freq: SyncTime,
+        one_shot: bool,
+        bipolar: bool,
+        hifi: bool,
+    },
+    /// saw wave going down
+    SawDown {
+        freq: SyncTime,
+        one_shot: bool,
+        bipolar: bool,
+        hifi: bool,
+    },
+    /// anti-log Triangle Wave
+    AntiLog {
+        freq: SyncTime,
+        one_shot: bool,
+        bipolar: bool,
+        hifi: bool,
+    },
+    /// anti-log saw wave going up
+    AntiLogUp {
+        freq: SyncTime,
+        one_shot: bool,
+        bipolar: bool,
+        hifi: bool,
+    },
+    /// anti-log saw wave going down
+    AntiLogDown {
+        freq: SyncTime,
+        one_shot: bool,
+        bipolar: bool,
+        hifi: bool,
+    },
+}
+
+impl TryFrom<LfoConfig> for ShapeLfo {
+    type Error = String;
+
+    fn try_from(conf: LfoConfig) -> Result<Self, Self::Error> {
+        let (shape, freq, one_shot, bipolar, hifi) = match conf {
+            LfoConfig::WaveTable { .. } => return Err("wave-tables aren't a built in shape".into()),
+            LfoConfig::Sin {
+                freq,
+                one_shot,
+                bipolar,
+                hifi,
+            } => (Shape::Sin, freq, one_shot, bipolar, hifi),
+            LfoConfig::Triangle {
+                freq,
+                one_shot,
+                bipolar,
+                hifi,
+            } => (Shape::Triangle, freq, one_shot, bipolar, hifi),
+            LfoConfig::SawUp {
+                freq,
+                one_shot,
+                bipolar,
+                hifi,
+            } => (Shape::SawUp, freq, one_shot, bipolar, hifi),
+            LfoConfig::SawDown {
+                freq,
+                one_shot,
+                bipolar,
+                hifi,
+            } => (Shape::SawDown, freq, one_shot, bipolar, hifi),
+            LfoConfig::AntiLog {
+                freq,
+                one_shot,
+                bipolar,
+                hifi,
+            } => (Shape::AntiLog, freq, one_shot, bipolar, hifi),
+            LfoConfig::AntiLogUp {
+                freq,
+                one_shot,
+                bipolar,
+                hifi,
+            } => (Shape::AntiLogUp, freq, one_shot, bipolar, hifi),
+            LfoConfig::AntiLogDown {
+                freq,
+                one_shot,
+                bipolar,
+                hifi,
+            } => (Shape::AntiLogDown, freq, one_shot, bipolar, hifi),
+        };
+
+        Ok(Self::new(shape, Phasor::new(freq, one_shot, bipolar, hifi)))
+    }
 }
 
 // #[pyclass]
@@ -70,48 +130,6 @@ pub enum LfoConfig {
 pub enum Lfo {
     /// wave-table lfo
     WaveTable(wavetable::WaveTable),
-    // /// sin wave
-    // Sin(SinLfo),
-    // /// triangle wave
-    // Triangle {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// saw wave going up
-    // SawUp {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// saw wave going down
-    // SawDown {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// anti-log Triangle Wave
-    // AntiLog {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// anti-log saw wave going up
-    // AntiLogUp {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
-    // /// anti-log saw wave going down
-    // AntiLogDown {
-    //     freq: f64,
-    //     one_shot: bool,
-    //     bipolar: bool,
-    //     hifi: bool,
-    // },
+    /// one of the built in shapes
+    Shape(ShapeLfo),
 }
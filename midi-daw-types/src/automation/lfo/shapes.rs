@@ -0,0 +1,184 @@
+use crate::automation::{
+    AutomationTrait, DEFAULT_TEMPO, HIFI_UPDATE_RATE, PULSE_UPDATE_RATE, SyncTime,
+};
+#[cfg(feature = "pyo3")]
+use pyo3::prelude::*;
+use std::f64::consts::TAU;
+
+/// how steep the anti-log curves are.
+const ANTI_LOG_CURVE: f64 = 4.0;
+
+/// the phase & options shared by the built in lfo shapes. every shape starts at its lowest point.
+#[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
+pub struct Phasor {
+    rate: SyncTime,
+    tempo: f64,
+    /// the frequency computed from rate & tempo
+    freq: f64,
+    one_shot: bool,
+    bipolar: bool,
+    update_rate: f64,
+    phase: f64,
+    last_value: f64,
+}
+
+impl Phasor {
+    /// hifi lfos assume they are updated at an audio sample rate until told otherwise.
+    pub fn new(rate: SyncTime, one_shot: bool, bipolar: bool, hifi: bool) -> Self {
+        Self {
+            rate,
+            tempo: DEFAULT_TEMPO,
+            freq: rate.hz(DEFAULT_TEMPO),
+            one_shot,
+            bipolar,
+            update_rate: if hifi {
+                HIFI_UPDATE_RATE
+            } else {
+                PULSE_UPDATE_RATE
+            },
+            phase: 0.0,
+            last_value: if bipolar { -1.0 } else { 0.0 },
+        }
+    }
+
+    /// computes the value at the current phase with shape, (a function that maps a phase from
+    /// 0.0 to 1.0 onto a value from 0.0 to 1.0), then advances the phase.
+    fn update(&mut self, shape: impl Fn(f64) -> f64) {
+        let value = shape(self.phase);
+        self.last_value = if self.bipolar {
+            value * 2.0 - 1.0
+        } else {
+            value
+        };
+
+        self.phase += self.freq / self.update_rate;
+
+        if self.phase >= 1.0 {
+            // one shot lfos hold their final value.
+            if self.one_shot {
+                self.phase = 1.0;
+            } else {
+                self.phase %= 1.0;
+            }
+        }
+    }
+
+    fn set_update_rate(&mut self, update_rate: f64) {
+        if update_rate > 0.0 {
+            self.update_rate = update_rate;
+        }
+    }
+
+    fn set_tempo(&mut self, tempo: f64) {
+        if tempo > 0.0 {
+            self.tempo = tempo;
+            self.freq = self.rate.hz(tempo);
+        }
+    }
+
+    fn reset(&mut self) {
+        if self.rate.is_synced() {
+            self.phase = 0.0;
+        }
+    }
+}
+
+/// the built in lfo shapes.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Shape {
+    /// sin wave
+    Sin,
+    /// triangle wave
+    Triangle,
+    /// saw wave going up
+    SawUp,
+    /// saw wave going down
+    SawDown,
+    /// anti-log triangle wave
+    AntiLog,
+    /// anti-log saw wave going up
+    AntiLogUp,
+    /// anti-log saw wave going down
+    AntiLogDown,
+}
+
+impl Shape {
+    /// maps a phase from 0.0 to 1.0 onto a value from 0.0 to 1.0.
+    pub fn value(self, phase: f64) -> f64 {
+        match self {
+            Self::Sin => (1.0 - (TAU * phase).cos()) / 2.0,
+            Self::Triangle => triangle(phase),
+            Self::SawUp => phase,
+            Self::SawDown => 1.0 - phase,
+            Self::AntiLog => anti_log(triangle(phase)),
+            Self::AntiLogUp => anti_log(phase),
+            Self::AntiLogDown => anti_log(1.0 - phase),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sin => "sin",
+            Self::Triangle => "triangle",
+            Self::SawUp => "saw-up",
+            Self::SawDown => "saw-down",
+            Self::AntiLog => "anti-log",
+            Self::AntiLogUp => "anti-log-up",
+            Self::AntiLogDown => "anti-log-down",
+        }
+    }
+}
+
+fn triangle(phase: f64) -> f64 {
+    1.0 - (2.0 * phase - 1.0).abs()
+}
+
+fn anti_log(x: f64) -> f64 {
+    ((ANTI_LOG_CURVE * x).exp() - 1.0) / (ANTI_LOG_CURVE.exp() - 1.0)
+}
+
+/// an lfo with one of the built in shapes.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(PartialEq, PartialOrd, Clone, Debug)]
+pub struct ShapeLfo {
+    phasor: Phasor,
+    shape: Shape,
+}
+
+impl ShapeLfo {
+    pub fn new(shape: Shape, phasor: Phasor) -> Self {
+        Self { phasor, shape }
+    }
+}
+
+impl AutomationTrait for ShapeLfo {
+    fn sub_type(&self) -> String {
+        self.shape.name().into()
+    }
+
+    fn update(&mut self) {
+        let shape = self.shape;
+
+        self.phasor.update(|phase| shape.value(phase));
+    }
+
+    fn get_value(&self) -> f64 {
+        self.phasor.last_value
+    }
+
+    fn set_update_rate(&mut self, update_rate: f64) {
+        self.phasor.set_update_rate(update_rate);
+    }
+
+    fn is_bipolar(&self) -> bool {
+        self.phasor.bipolar
+    }
+
+    fn set_tempo(&mut self, tempo: f64) {
+        self.phasor.set_tempo(tempo);
+    }
+
+    fn reset(&mut self) {
+        self.phasor.reset();
+    }
+}
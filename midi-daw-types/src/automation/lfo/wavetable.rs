@@ -1,4 +1,4 @@
-use crate::automation::AutomationTrait;
+use crate::automation::{AutomationTrait, DEFAULT_TEMPO, SyncTime};
 #[cfg(feature = "pyo3")]
 use pyo3::prelude::*;
 use std::sync::Arc;
@@ -8,6 +8,8 @@ use std::sync::Arc;
 #[derive(PartialEq, PartialOrd, Clone, Debug)]
 pub struct WaveTable {
     sample_rate: f64,
+    rate: SyncTime,
+    tempo: f64,
     index: f64,
     index_increment: f64,
     wavetable: Arc<[f64]>,
@@ -15,8 +17,8 @@ pub struct WaveTable {
 }
 
 impl AutomationTrait for WaveTable {
-    fn sub_type(&self) -> impl Into<String> {
-        "wavetable"
+    fn sub_type(&self) -> String {
+        "wavetable".into()
     }
 
     fn update(&mut self) {
@@ -26,6 +28,28 @@ impl AutomationTrait for WaveTable {
     fn get_value(&self) -> f64 {
         self.last_sample
     }
+
+    fn set_update_rate(&mut self, update_rate: f64) {
+        if update_rate <= 0.0 {
+            return;
+        }
+
+        self.sample_rate = update_rate;
+        self.set_increment();
+    }
+
+    fn set_tempo(&mut self, tempo: f64) {
+        if tempo > 0.0 {
+            self.tempo = tempo;
+            self.set_increment();
+        }
+    }
+
+    fn reset(&mut self) {
+        if self.rate.is_synced() {
+            self.index = 0.0;
+        }
+    }
 }
 
 impl WaveTable {
@@ -34,6 +58,8 @@ impl WaveTable {
 
         Self {
             sample_rate,
+            rate: SyncTime::Hz(0.0),
+            tempo: DEFAULT_TEMPO,
             index: 0.0,
             index_increment: 0.0,
             wavetable,
@@ -42,10 +68,21 @@ impl WaveTable {
     }
 
     pub fn set_frequency(&mut self, frequency: f64) {
-        self.index_increment = frequency * self.wavetable.len() as f64 / self.sample_rate;
+        self.set_rate(SyncTime::Hz(frequency));
+    }
+
+    /// sets how often the wavetable cycles. resets the phase.
+    pub fn set_rate(&mut self, rate: SyncTime) {
+        self.rate = rate;
+        self.set_increment();
         self.index = 0.0;
     }
 
+    fn set_increment(&mut self) {
+        self.index_increment =
+            self.rate.hz(self.tempo) * self.wavetable.len() as f64 / self.sample_rate;
+    }
+
     pub fn get_sample(&mut self) -> f64 {
         let sample = self.lerp();
 
@@ -1,19 +1,78 @@
 use enum_dispatch::enum_dispatch;
+use envelope::{
+    Envelope,
+    adsr::{self, Adsr},
+};
 use hound::{SampleFormat, WavReader};
 use lfo::{Lfo, wavetable};
 #[cfg(feature = "pyo3")]
 use pyo3::{exceptions::PyValueError, prelude::*};
 use serde::{Deserialize, Serialize};
 
-use crate::automation::lfo::wavetable::WaveTable;
+use crate::{
+    MidiMsg, NoteDuration,
+    automation::lfo::{shapes::ShapeLfo, wavetable::WaveTable},
+};
 
-// pub mod envelope;
+pub mod envelope;
 pub mod lfo;
+#[cfg(test)]
+mod tests;
+
+/// the update rate assumed by hifi automations, (an audio sample rate).
+pub const HIFI_UPDATE_RATE: f64 = 48_000.0;
+/// the update rate assumed by automations that update every sync pulse (24 BPQ at 99 BPM).
+pub const PULSE_UPDATE_RATE: f64 = (99.0 / 60.0) * 24.0;
+/// the tempo assumed by tempo synced automations until told otherwise.
+pub const DEFAULT_TEMPO: f64 = 99.0;
+
+/// a rate or length of time. the musical variants follow the tempo.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Copy, Debug)]
+pub enum SyncTime {
+    /// a frequency, (ie: one cycle lasts 1/hz seconds)
+    Hz(f64),
+    /// a number of seconds
+    Secs(f64),
+    /// a musical note length, (ie: one cycle per NoteDuration::Wn(2))
+    Note(NoteDuration),
+    /// a number of sequencer steps, (sixteenth notes)
+    Steps(usize),
+}
+
+impl SyncTime {
+    /// the length of time in seconds at tempo.
+    pub fn secs(&self, tempo: f64) -> f64 {
+        match *self {
+            Self::Hz(hz) => 1.0 / hz,
+            Self::Secs(secs) => secs,
+            Self::Note(dur) => dur.secs(tempo),
+            Self::Steps(steps) => NoteDuration::Sn(1).secs(tempo) * steps as f64,
+        }
+    }
+
+    /// the frequency in hertz at tempo.
+    pub fn hz(&self, tempo: f64) -> f64 {
+        match *self {
+            Self::Hz(hz) => hz,
+            _ => {
+                let secs = self.secs(tempo);
+
+                if secs > 0.0 { 1.0 / secs } else { 0.0 }
+            }
+        }
+    }
+
+    /// true if this follows the tempo.
+    pub fn is_synced(&self) -> bool {
+        matches!(self, Self::Note(_) | Self::Steps(_))
+    }
+}
 
 #[enum_dispatch]
 pub trait AutomationTrait /*: PyClass */ {
     // fn automation_type(&self) -> impl Into<String>;
-    fn sub_type(&self) -> impl Into<String>;
+    fn sub_type(&self) -> String;
     /// used to update the state of the automation
     fn update(&mut self);
     /// used to get the last value of automation
@@ -22,6 +81,20 @@ pub trait AutomationTrait /*: PyClass */ {
         self.update();
         self.get_value()
     }
+    /// tells the automation how many times a second `update` will be called.
+    fn set_update_rate(&mut self, _update_rate: f64) {}
+    /// true if the value swings between -1.0 & 1.0, false if it stays between 0.0 & 1.0.
+    fn is_bipolar(&self) -> bool {
+        true
+    }
+    /// starts the automation, used by envelopes when a note starts.
+    fn gate_on(&mut self) {}
+    /// releases the automation, used by envelopes when a note stops.
+    fn gate_off(&mut self) {}
+    /// tells the automation the tempo, used by automations with tempo synced timing.
+    fn set_tempo(&mut self, _tempo: f64) {}
+    /// re-phases tempo synced automations to the start of the bar.
+    fn reset(&mut self) {}
 }
 
 // #[pyclass]
@@ -30,7 +103,7 @@ pub trait AutomationTrait /*: PyClass */ {
 #[enum_dispatch(AutomationTrait)]
 pub enum AutomationTypes {
     Lfo(lfo::Lfo),
-    // EnvelopeGen(envelope::Envelope),
+    EnvelopeGen(envelope::Envelope),
 }
 
 // #[pyclass]
@@ -38,7 +111,7 @@ pub enum AutomationTypes {
 #[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Debug)]
 pub enum AutomationConf {
     Lfo(lfo::LfoConfig),
-    // EnvelopeGen(envelope::EnvConfig),
+    EnvelopeGen(envelope::EnvConfig),
 }
 
 impl TryFrom<AutomationConf> for AutomationTypes {
@@ -82,11 +155,60 @@ impl TryFrom<AutomationConf> for AutomationTypes {
                 // build WaveTable
                 let mut wavetable =
                     WaveTable::new(samples.into(), reader.spec().sample_rate as f64);
-                wavetable.set_frequency(freq);
+                wavetable.set_rate(freq);
 
                 // set WaveTable frequency to freq
                 Ok(AutomationTypes::Lfo(lfo::Lfo::WaveTable(wavetable)))
-            } // AutomationConf::EnvelopeGen() => {}
+            }
+            AutomationConf::Lfo(conf) => Ok(AutomationTypes::Lfo(lfo::Lfo::Shape(
+                ShapeLfo::try_from(conf)?,
+            ))),
+            AutomationConf::EnvelopeGen(envelope::EnvConfig::ADSR {
+                atk,
+                dcy,
+                sus,
+                rel,
+                hifi,
+            }) => Ok(AutomationTypes::EnvelopeGen(envelope::Envelope::Adsr(
+                Adsr::new(atk, dcy, sus, rel, hifi),
+            ))),
+            AutomationConf::EnvelopeGen(envelope::EnvConfig::AR { atk, rel, hifi }) => {
+                Ok(AutomationTypes::EnvelopeGen(envelope::Envelope::Adsr(
+                    Adsr::new_ar(atk, rel, hifi),
+                )))
+            }
+        }
+    }
+}
+
+/// the midi parameter that a server side automation drives.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum AutomationTarget {
+    /// a control change, the automation is scaled to sweep between min & max.
+    CC { control: u8, min: u8, max: u8 },
+    /// pitch bend, the automation is scaled to sweep between min & max.
+    PitchBend { min: u16, max: u16 },
+}
+
+impl AutomationTarget {
+    /// scales an automation value onto this target.
+    pub fn scale(&self, value: f64, bipolar: bool) -> MidiMsg {
+        let amt = if bipolar {
+            (value.clamp(-1.0, 1.0) + 1.0) / 2.0
+        } else {
+            value.clamp(0.0, 1.0)
+        };
+        let lerp = |min: f64, max: f64| (min + (max - min) * amt).round();
+
+        match *self {
+            Self::CC { control, min, max } => MidiMsg::CC {
+                control,
+                value: lerp(min as f64, max as f64).clamp(0.0, 127.0) as u8,
+            },
+            Self::PitchBend { min, max } => MidiMsg::PitchBend {
+                bend: lerp(min as f64, max as f64).clamp(0.0, 16383.0) as u16,
+            },
         }
     }
 }
@@ -132,10 +254,26 @@ impl Automation {
         self.automation.step()
     }
 
+    pub fn gate_on(&mut self) {
+        self.automation.gate_on()
+    }
+
+    pub fn gate_off(&mut self) {
+        self.automation.gate_off()
+    }
+
+    pub fn set_tempo(&mut self, tempo: f64) {
+        self.automation.set_tempo(tempo)
+    }
+
+    pub fn reset(&mut self) {
+        self.automation.reset()
+    }
+
     pub fn get_repr(&self) -> String {
         match self.automation.clone() {
-            AutomationTypes::Lfo(lfo) => format!("lfo:{}", lfo.sub_type().into()),
-            // AutomationTypes::EnvelopeGen(env) => format!("env:{}", env.sub_type().into()),
+            AutomationTypes::Lfo(lfo) => format!("lfo:{}", lfo.sub_type()),
+            AutomationTypes::EnvelopeGen(env) => format!("env:{}", env.sub_type()),
         }
     }
 
@@ -144,6 +282,6 @@ impl Automation {
     // }
 
     pub fn sub_type(&self) -> String {
-        self.automation.sub_type().into()
+        self.automation.sub_type()
     }
 }
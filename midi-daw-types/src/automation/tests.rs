@@ -0,0 +1,176 @@
+use super::*;
+use crate::automation::lfo::shapes::{Phasor, Shape, ShapeLfo};
+
+/// how many updates a second the automations are stepped at, so an automation that lasts one
+/// second moves a quarter of the way each update.
+const UPDATE_RATE: f64 = 4.0;
+
+fn assert_near(got: f64, expected: f64) {
+    assert!(
+        (got - expected).abs() < 1e-9,
+        "expected {expected}, got {got}"
+    );
+}
+
+/// steps automation n times, returning the values.
+fn steps(automation: &mut impl AutomationTrait, n: usize) -> Vec<f64> {
+    (0..n).map(|_| automation.step()).collect()
+}
+
+fn assert_steps(automation: &mut impl AutomationTrait, expected: &[f64]) {
+    steps(automation, expected.len())
+        .into_iter()
+        .zip(expected)
+        .for_each(|(got, expected)| assert_near(got, *expected));
+}
+
+fn lfo(shape: Shape, rate: SyncTime, one_shot: bool, bipolar: bool) -> ShapeLfo {
+    let mut lfo = ShapeLfo::new(shape, Phasor::new(rate, one_shot, bipolar, false));
+    lfo.set_update_rate(UPDATE_RATE);
+
+    lfo
+}
+
+fn adsr(atk: SyncTime, dcy: SyncTime, sus: f64, rel: SyncTime) -> Adsr {
+    let mut env = Adsr::new(atk, dcy, sus, rel, false);
+    env.set_update_rate(UPDATE_RATE);
+
+    env
+}
+
+#[test]
+fn shape_values() {
+    let anti_log = |x: f64| ((4.0 * x).exp() - 1.0) / (4.0_f64.exp() - 1.0);
+    let expected = [
+        (Shape::Sin, [0.0, 0.5, 1.0]),
+        (Shape::Triangle, [0.0, 0.5, 1.0]),
+        (Shape::SawUp, [0.0, 0.25, 0.5]),
+        (Shape::SawDown, [1.0, 0.75, 0.5]),
+        (Shape::AntiLog, [0.0, anti_log(0.5), 1.0]),
+        (Shape::AntiLogUp, [0.0, anti_log(0.25), anti_log(0.5)]),
+        (Shape::AntiLogDown, [1.0, anti_log(0.75), anti_log(0.5)]),
+    ];
+
+    for (shape, values) in expected {
+        for (phase, value) in [0.0, 0.25, 0.5].into_iter().zip(values) {
+            assert_near(shape.value(phase), value);
+        }
+    }
+}
+
+#[test]
+fn lfos_loop() {
+    let mut saw = lfo(Shape::SawUp, SyncTime::Hz(1.0), false, false);
+
+    assert_steps(&mut saw, &[0.0, 0.25, 0.5, 0.75, 0.0, 0.25]);
+}
+
+#[test]
+fn one_shots_hold_their_last_value() {
+    let mut saw = lfo(Shape::SawUp, SyncTime::Hz(1.0), true, false);
+
+    assert_steps(&mut saw, &[0.0, 0.25, 0.5, 0.75, 1.0, 1.0, 1.0]);
+}
+
+#[test]
+fn bipolar_lfos_swing_around_zero() {
+    let mut saw = lfo(Shape::SawUp, SyncTime::Hz(1.0), false, true);
+
+    assert!(saw.is_bipolar());
+    assert_near(saw.get_value(), -1.0);
+    assert_steps(&mut saw, &[-1.0, -0.5, 0.0, 0.5]);
+    assert!(!lfo(Shape::SawUp, SyncTime::Hz(1.0), false, false).is_bipolar());
+}
+
+#[test]
+fn adsr_stages() {
+    let secs = SyncTime::Secs(1.0);
+    let mut env = adsr(secs, secs, 0.5, secs);
+
+    // idle until the gate opens.
+    assert_steps(&mut env, &[0.0, 0.0]);
+
+    env.gate_on();
+    // attack, then decay to the sustain level & hold it.
+    assert_steps(&mut env, &[0.25, 0.5, 0.75, 1.0]);
+    assert_steps(&mut env, &[0.875, 0.75, 0.625, 0.5]);
+    assert_steps(&mut env, &[0.5, 0.5]);
+
+    env.gate_off();
+    // release from the sustain level, then stay idle.
+    assert_steps(&mut env, &[0.375, 0.25, 0.125, 0.0]);
+    assert_steps(&mut env, &[0.0]);
+    assert!(!env.is_bipolar());
+}
+
+#[test]
+fn releasing_mid_attack_starts_from_the_current_level() {
+    let secs = SyncTime::Secs(1.0);
+    let mut env = adsr(secs, secs, 0.5, secs);
+
+    env.gate_on();
+    assert_steps(&mut env, &[0.25, 0.5]);
+
+    env.gate_off();
+    assert_steps(&mut env, &[0.375, 0.25, 0.125, 0.0, 0.0]);
+}
+
+#[test]
+fn ar_envelopes_hold_full_level() {
+    let mut env = Adsr::new_ar(SyncTime::Secs(1.0), SyncTime::Secs(1.0), false);
+    env.set_update_rate(UPDATE_RATE);
+
+    assert_eq!(env.sub_type(), "ar");
+
+    env.gate_on();
+    assert_steps(&mut env, &[0.25, 0.5, 0.75, 1.0, 1.0, 1.0]);
+
+    env.gate_off();
+    assert_steps(&mut env, &[0.75, 0.5, 0.25, 0.0]);
+}
+
+#[test]
+fn synced_rates_follow_the_tempo() {
+    // a quarter note lasts a second at 60 BPM & half a second at 120 BPM.
+    for rate in [SyncTime::Note(NoteDuration::Qn(1)), SyncTime::Steps(4)] {
+        let mut saw = lfo(Shape::SawUp, rate, false, false);
+        saw.set_tempo(60.0);
+
+        assert_steps(&mut saw, &[0.0, 0.25]);
+
+        saw.set_tempo(120.0);
+        assert_steps(&mut saw, &[0.5, 0.0, 0.5]);
+    }
+
+    let qn = SyncTime::Note(NoteDuration::Qn(1));
+    let mut env = adsr(qn, qn, 1.0, qn);
+    env.set_tempo(120.0);
+    env.gate_on();
+
+    assert_steps(&mut env, &[0.5, 1.0]);
+}
+
+#[test]
+fn unsynced_rates_ignore_the_tempo() {
+    let mut saw = lfo(Shape::SawUp, SyncTime::Hz(1.0), false, false);
+    saw.set_tempo(120.0);
+
+    assert_steps(&mut saw, &[0.0, 0.25, 0.5]);
+}
+
+#[test]
+fn reset_rephases_synced_lfos() {
+    let mut synced = lfo(Shape::SawUp, SyncTime::Steps(4), false, false);
+    synced.set_tempo(60.0);
+    assert_steps(&mut synced, &[0.0, 0.25, 0.5]);
+
+    synced.reset();
+    assert_steps(&mut synced, &[0.0, 0.25]);
+
+    // free running lfos keep their phase.
+    let mut free = lfo(Shape::SawUp, SyncTime::Hz(1.0), false, false);
+    assert_steps(&mut free, &[0.0, 0.25]);
+
+    free.reset();
+    assert_steps(&mut free, &[0.5]);
+}
@@ -1,5 +1,8 @@
 #[cfg(feature = "pyo3")]
-use crate::automation::{Automation, AutomationConf, AutomationTypes, lfo::LfoConfig};
+use crate::automation::{
+    Automation, AutomationTypes, SyncTime, envelope::EnvConfig, lfo::LfoConfig,
+};
+use crate::automation::{AutomationConf, AutomationTarget};
 use bincode::{
     Decode, Encode,
     error::{DecodeError, EncodeError},
@@ -8,7 +11,7 @@ use midi_msg::Channel;
 #[cfg(feature = "pyo3")]
 use pyo3::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 use tracing::warn;
 
 pub const UDS_SERVER_PATH: &str = "/tmp/midi-daw.sock";
@@ -20,6 +23,9 @@ pub type Step = Vec<MidiMsg>;
 pub type SequenceName = String;
 
 pub mod automation;
+pub mod smf;
+#[cfg(test)]
+mod tests;
 
 #[cfg_attr(feature = "pyo3", pyclass)]
 #[cfg_attr(feature = "pyo3", pyo3(get_all, set_all))]
@@ -270,6 +276,14 @@ pub enum NoteDuration {
     Sn(u8),
     Tn(u8),
     S4n(u8),
+    /// quarter note triplets, (three in the time of a half note).
+    QnT(u8),
+    /// eighth note triplets, (three in the time of a quarter note).
+    EnT(u8),
+    /// sixteenth note triplets, (three in the time of an eighth note).
+    SnT(u8),
+    /// thirty-second note triplets, (three in the time of a sixteenth note).
+    TnT(u8),
 }
 
 impl Default for NoteDuration {
@@ -283,6 +297,59 @@ impl NoteDuration {
         Self::default()
     }
 
+    /// the length of this duration in quarter notes.
+    pub fn quarter_notes(&self) -> f64 {
+        let (n, quarters) = match *self {
+            Self::Wn(n) => (n, 4.0),
+            Self::Hn(n) => (n, 2.0),
+            Self::Qn(n) => (n, 1.0),
+            Self::En(n) => (n, 0.5),
+            Self::Sn(n) => (n, 0.25),
+            Self::Tn(n) => (n, 0.125),
+            Self::S4n(n) => (n, 0.0625),
+            Self::QnT(n) => (n, 2.0 / 3.0),
+            Self::EnT(n) => (n, 1.0 / 3.0),
+            Self::SnT(n) => (n, 1.0 / 6.0),
+            Self::TnT(n) => (n, 1.0 / 12.0),
+        };
+
+        n as f64 * quarters
+    }
+
+    /// the length of this duration in seconds at tempo.
+    pub fn secs(&self, tempo: f64) -> f64 {
+        self.quarter_notes() * 60.0 / tempo
+    }
+
+    /// the duration closest to quarter_notes long. equal lengths are written with the longest
+    /// note value, (ie: En(1) rather then Sn(2)), & straight notes are prefered over triplets.
+    pub fn nearest(quarter_notes: f64) -> Self {
+        let kinds: [fn(u8) -> Self; 11] = [
+            Self::Wn,
+            Self::Hn,
+            Self::Qn,
+            Self::En,
+            Self::Sn,
+            Self::Tn,
+            Self::S4n,
+            Self::QnT,
+            Self::EnT,
+            Self::SnT,
+            Self::TnT,
+        ];
+
+        kinds
+            .into_iter()
+            .flat_map(|kind| (1..=u8::MAX).map(kind))
+            .min_by(|a, b| {
+                let a = (a.quarter_notes() - quarter_notes).abs();
+                let b = (b.quarter_notes() - quarter_notes).abs();
+
+                a.total_cmp(&b)
+            })
+            .unwrap_or_default()
+    }
+
     pub fn json(&self) -> String {
         let Ok(res) = serde_json::to_string(self) else {
             return String::new();
@@ -347,6 +414,20 @@ pub enum MidiMsg {
     // Volume { amt: u16 },
 }
 
+impl MidiMsg {
+    /// true if both messages control the same thing, (ie: the same note or CC control number),
+    /// regardless of their values.
+    pub fn same_kind(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::PlayNote { note: a, .. }, Self::PlayNote { note: b, .. }) => a == b,
+            (Self::StopNote { note: a }, Self::StopNote { note: b }) => a == b,
+            (Self::PitchBend { .. }, Self::PitchBend { .. }) => true,
+            (Self::CC { control: a, .. }, Self::CC { control: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
 // #[pymethods]
 #[cfg_attr(feature = "pyo3", pymethods)]
 impl MidiMsg {
@@ -361,6 +442,14 @@ impl MidiMsg {
 //     Ok((a + b).to_string())
 // }
 
+/// reads the standard midi file in bytes into sequences, one per track. see
+/// smf::sequences_from_smf.
+#[cfg(feature = "pyo3")]
+#[pyfunction]
+fn sequences_from_smf(name: String, bytes: Vec<u8>) -> PyResult<Vec<Sequence>> {
+    smf::sequences_from_smf(&name, &bytes).map_err(pyo3::exceptions::PyValueError::new_err)
+}
+
 /// gets midi note as a u8 from a string name.
 #[cfg_attr(feature = "pyo3", pyfunction)]
 pub fn note_from_str(name: String) -> Option<u8> {
@@ -587,16 +676,17 @@ impl RmNoteBody {
     }
 }
 
+/// turns midi clock, start/stop & song position output on or off for a midi device.
 #[cfg_attr(feature = "pyo3", pyclass)]
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
-pub struct SetDevBody {
-    pub sequence: String,
+pub struct MidiClockOutBody {
     pub midi_dev: String,
+    pub on: bool,
 }
 
-impl SetDevBody {
-    pub fn new(sequence: String, midi_dev: String) -> Self {
-        Self { sequence, midi_dev }
+impl MidiClockOutBody {
+    pub fn new(midi_dev: String, on: bool) -> Self {
+        Self { midi_dev, on }
     }
 
     pub fn json(&self) -> String {
@@ -610,10 +700,10 @@ impl SetDevBody {
 
 #[cfg(feature = "pyo3")]
 #[pymethods]
-impl SetDevBody {
+impl MidiClockOutBody {
     #[new]
-    fn new_py(sequence: String, midi_dev: String) -> Self {
-        Self::new(sequence, midi_dev)
+    fn new_py(midi_dev: String, on: bool) -> Self {
+        Self::new(midi_dev, on)
     }
 
     #[pyo3(name = "json")]
@@ -622,16 +712,26 @@ impl SetDevBody {
     }
 }
 
+/// where the sequencer gets its sync pulses from.
 #[cfg_attr(feature = "pyo3", pyclass)]
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
-pub struct GetSequenceQuery {
-    pub sequence: String,
-    // pub midi_dev: String,
+pub enum ClockSource {
+    /// the server's own clock, following the tempo set with /tempo
+    Internal(),
+    /// the midi clock, (and start/stop), coming in from midi_dev
+    External { midi_dev: String },
 }
 
-impl GetSequenceQuery {
-    pub fn new(sequence: String) -> Self {
-        Self { sequence }
+impl Default for ClockSource {
+    fn default() -> Self {
+        Self::Internal()
+    }
+}
+
+impl ClockSource {
+    /// true if the sequencer follows its own clock.
+    pub fn is_internal(&self) -> bool {
+        matches!(self, Self::Internal())
     }
 
     pub fn json(&self) -> String {
@@ -645,10 +745,80 @@ impl GetSequenceQuery {
 
 #[cfg(feature = "pyo3")]
 #[pymethods]
-impl GetSequenceQuery {
+impl ClockSource {
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+/// when queued sequences start & stop playing.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum LaunchQuant {
+    /// on the next pulse
+    Immediate(),
+    /// on the sequence's next step
+    Step(),
+    /// on the next quarter note
+    Beat(),
+    /// on the next multiple of n bars, (ie: Bars(1) is the next bar)
+    Bars(usize),
+}
+
+impl Default for LaunchQuant {
+    fn default() -> Self {
+        Self::Bars(1)
+    }
+}
+
+impl LaunchQuant {
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl LaunchQuant {
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+/// starts or queues a stop of sequences. quant overrides the global launch quantization.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct LaunchBody {
+    pub sequences: Vec<String>,
+    pub quant: Option<LaunchQuant>,
+}
+
+impl LaunchBody {
+    pub fn new(sequences: Vec<String>, quant: Option<LaunchQuant>) -> Self {
+        Self { sequences, quant }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl LaunchBody {
     #[new]
-    fn new_py(sequence: String) -> Self {
-        Self::new(sequence)
+    fn new_py(sequences: Vec<String>, quant: Option<LaunchQuant>) -> Self {
+        Self::new(sequences, quant)
     }
 
     #[pyo3(name = "json")]
@@ -657,16 +827,23 @@ impl GetSequenceQuery {
     }
 }
 
+/// adds an arbitrary midi command, (ie: a CC or pitch bend), to a step of a sequence. replaces a
+/// command of the same kind, (same CC control number), on that step.
 #[cfg_attr(feature = "pyo3", pyclass)]
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
-pub struct RenameSequenceBody {
-    pub old_name: String,
-    pub new_name: String,
+pub struct AddCmdBody {
+    pub sequence: String,
+    pub step: usize,
+    pub cmd: MidiMsg,
 }
 
-impl RenameSequenceBody {
-    pub fn new(old_name: String, new_name: String) -> Self {
-        Self { old_name, new_name }
+impl AddCmdBody {
+    pub fn new(sequence: String, step: usize, cmd: MidiMsg) -> Self {
+        Self {
+            sequence,
+            step,
+            cmd,
+        }
     }
 
     pub fn json(&self) -> String {
@@ -680,10 +857,10 @@ impl RenameSequenceBody {
 
 #[cfg(feature = "pyo3")]
 #[pymethods]
-impl RenameSequenceBody {
+impl AddCmdBody {
     #[new]
-    fn new_py(old_name: String, new_name: String) -> Self {
-        Self::new(old_name, new_name)
+    fn new_py(sequence: String, step: usize, cmd: MidiMsg) -> Self {
+        Self::new(sequence, step, cmd)
     }
 
     #[pyo3(name = "json")]
@@ -692,16 +869,22 @@ impl RenameSequenceBody {
     }
 }
 
+/// removes a midi command from a step of a sequence. CCs are matched by control number only.
 #[cfg_attr(feature = "pyo3", pyclass)]
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
-pub struct SetChannelBody {
+pub struct RmCmdBody {
     pub sequence: String,
-    pub channel: MidiChannel,
+    pub step: usize,
+    pub cmd: MidiMsg,
 }
 
-impl SetChannelBody {
-    pub fn new(sequence: String, channel: MidiChannel) -> Self {
-        Self { sequence, channel }
+impl RmCmdBody {
+    pub fn new(sequence: String, step: usize, cmd: MidiMsg) -> Self {
+        Self {
+            sequence,
+            step,
+            cmd,
+        }
     }
 
     pub fn json(&self) -> String {
@@ -715,10 +898,10 @@ impl SetChannelBody {
 
 #[cfg(feature = "pyo3")]
 #[pymethods]
-impl SetChannelBody {
+impl RmCmdBody {
     #[new]
-    fn new_py(sequence: String, channel: MidiChannel) -> Self {
-        Self::new(sequence, channel)
+    fn new_py(sequence: String, step: usize, cmd: MidiMsg) -> Self {
+        Self::new(sequence, step, cmd)
     }
 
     #[pyo3(name = "json")]
@@ -727,45 +910,208 @@ impl SetChannelBody {
     }
 }
 
+/// what recording does to the notes already in a sequence.
 #[cfg_attr(feature = "pyo3", pyclass)]
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub struct Sequence {
-    pub name: SequenceName,
-    pub steps: Vec<Step>,
-    pub midi_dev: String,
-    pub channel: MidiChannel,
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default, Clone, Copy, Debug)]
+pub enum RecordMode {
+    /// recorded notes are added on top of the existing ones
+    #[default]
+    Overdub,
+    /// the notes on a step are cleared the first time recording passes over it
+    Replace,
 }
 
-impl Sequence {
-    pub fn new(name: String) -> Self {
-        let mut seq = Self::default();
-        seq.name = name;
+/// a named group of sequences that are launched together.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Debug)]
+pub struct Scene {
+    pub name: String,
+    pub sequences: Vec<SequenceName>,
+    /// the tempo to switch to when the scene launches
+    pub tempo: Option<f64>,
+}
 
-        seq
+impl Scene {
+    pub fn new(name: String, sequences: Vec<SequenceName>, tempo: Option<f64>) -> Self {
+        Self {
+            name,
+            sequences,
+            tempo,
+        }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
     }
 }
 
-impl Default for Sequence {
-    fn default() -> Self {
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl Scene {
+    #[new]
+    fn new_py(name: String, sequences: Vec<SequenceName>, tempo: Option<f64>) -> Self {
+        Self::new(name, sequences, tempo)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+/// a section of an arrangement, its sequences play for bars bars.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Debug)]
+pub struct Section {
+    pub sequences: Vec<SequenceName>,
+    pub bars: usize,
+    /// the tempo to switch to when the section starts
+    pub tempo: Option<f64>,
+}
+
+impl Section {
+    pub fn new(sequences: Vec<SequenceName>, bars: usize, tempo: Option<f64>) -> Self {
         Self {
-            name: "Default-Sequence".into(),
-            steps: (0..16).map(|_| Vec::default()).collect(),
-            midi_dev: "Midi Through:0".into(),
-            channel: MidiChannel::Ch1,
+            sequences,
+            bars,
+            tempo,
+        }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl Section {
+    #[new]
+    fn new_py(sequences: Vec<SequenceName>, bars: usize, tempo: Option<f64>) -> Self {
+        Self::new(sequences, bars, tempo)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+/// a song, the sections are played in order one after the other.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Debug, Default)]
+pub struct Arrangement {
+    pub sections: Vec<Section>,
+    /// the first & last sections, (inclusive), to repeat once the last one is reached
+    pub loop_region: Option<(usize, usize)>,
+}
+
+impl Arrangement {
+    pub fn new(sections: Vec<Section>, loop_region: Option<(usize, usize)>) -> Self {
+        Self {
+            sections,
+            loop_region,
+        }
+    }
+
+    /// the section to play after the section at index, if any.
+    pub fn next_section(&self, index: usize) -> Option<usize> {
+        match self.loop_region {
+            Some((first, last)) if index == last && first <= last && last < self.sections.len() => {
+                Some(first)
+            }
+            _ => Some(index + 1).filter(|next| *next < self.sections.len()),
         }
     }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl Arrangement {
+    #[new]
+    fn new_py(sections: Vec<Section>, loop_region: Option<(usize, usize)>) -> Self {
+        Self::new(sections, loop_region)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
 }
 
+/// plays a scene's sequences & stops everything else. quant overrides the global launch
+/// quantization.
 #[cfg_attr(feature = "pyo3", pyclass)]
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
-pub struct ChangeLenByBody {
+pub struct LaunchSceneBody {
+    pub scene: String,
+    pub quant: Option<LaunchQuant>,
+}
+
+impl LaunchSceneBody {
+    pub fn new(scene: String, quant: Option<LaunchQuant>) -> Self {
+        Self { scene, quant }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl LaunchSceneBody {
+    #[new]
+    fn new_py(scene: String, quant: Option<LaunchQuant>) -> Self {
+        Self::new(scene, quant)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+/// arms a sequence to record the notes played on a midi input.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct RecordBody {
     pub sequence: String,
-    pub amt: isize,
+    /// the midi input to record from
+    pub midi_dev: String,
+    pub mode: RecordMode,
+    /// the number of bars to wait before recording starts
+    pub count_in: usize,
 }
 
-impl ChangeLenByBody {
-    pub fn new(sequence: String, amt: isize) -> Self {
-        Self { sequence, amt }
+impl RecordBody {
+    pub fn new(sequence: String, midi_dev: String, mode: RecordMode, count_in: usize) -> Self {
+        Self {
+            sequence,
+            midi_dev,
+            mode,
+            count_in,
+        }
     }
 
     pub fn json(&self) -> String {
@@ -779,10 +1125,10 @@ impl ChangeLenByBody {
 
 #[cfg(feature = "pyo3")]
 #[pymethods]
-impl ChangeLenByBody {
+impl RecordBody {
     #[new]
-    fn new_py(sequence: String, amt: isize) -> Self {
-        Self::new(sequence, amt)
+    fn new_py(sequence: String, midi_dev: String, mode: RecordMode, count_in: usize) -> Self {
+        Self::new(sequence, midi_dev, mode, count_in)
     }
 
     #[pyo3(name = "json")]
@@ -791,13 +1137,857 @@ impl ChangeLenByBody {
     }
 }
 
+/// the kinds of midi message a route can drop.
 #[cfg_attr(feature = "pyo3", pyclass)]
-#[derive(Serialize, Deserialize, Encode, Decode, PartialEq, PartialOrd, Clone, Debug)]
-pub enum MsgFromServer {
-    /// a single sync pulse
-    SyncPulse {
-        /// the number of pulses
-        pulse_count: usize,
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
+pub enum MidiMsgKind {
+    /// note on & note off
+    Notes,
+    /// control changes, (including channel mode messages)
+    CC,
+    PitchBend,
+    /// poly & channel pressure
+    Aftertouch,
+    ProgramChange,
+    /// clock, transport, song position, etc
+    System,
+}
+
+/// forwards the midi from an input to an output.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct Route {
+    /// the midi input to forward from
+    pub from_dev: String,
+    /// only forward this channel. None forwards every channel
+    pub from_channel: Option<MidiChannel>,
+    /// the midi output to forward to, (virtual devices included)
+    pub to_dev: String,
+    /// the channel to remap to. None keeps the channel
+    pub to_channel: Option<MidiChannel>,
+    /// the number of semitones to transpose notes by
+    pub transpose: i8,
+    /// the kinds of midi message not to forward
+    pub drop: Vec<MidiMsgKind>,
+}
+
+impl Route {
+    pub fn new(
+        from_dev: String,
+        from_channel: Option<MidiChannel>,
+        to_dev: String,
+        to_channel: Option<MidiChannel>,
+        transpose: i8,
+        drop: Vec<MidiMsgKind>,
+    ) -> Self {
+        Self {
+            from_dev,
+            from_channel,
+            to_dev,
+            to_channel,
+            transpose,
+            drop,
+        }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl Route {
+    #[new]
+    fn new_py(
+        from_dev: String,
+        from_channel: Option<MidiChannel>,
+        to_dev: String,
+        to_channel: Option<MidiChannel>,
+        transpose: i8,
+        drop: Vec<MidiMsgKind>,
+    ) -> Self {
+        Self::new(from_dev, from_channel, to_dev, to_channel, transpose, drop)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+/// a standard midi file to import as sequences.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct ImportSmfBody {
+    /// used to name the sequences made from tracks without names
+    pub name: String,
+    /// the contents of the .mid file
+    pub smf: Vec<u8>,
+}
+
+impl ImportSmfBody {
+    pub fn new(name: String, smf: Vec<u8>) -> Self {
+        Self { name, smf }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl ImportSmfBody {
+    #[new]
+    fn new_py(name: String, smf: Vec<u8>) -> Self {
+        Self::new(name, smf)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct SetDevBody {
+    pub sequence: String,
+    pub midi_dev: String,
+}
+
+impl SetDevBody {
+    pub fn new(sequence: String, midi_dev: String) -> Self {
+        Self { sequence, midi_dev }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl SetDevBody {
+    #[new]
+    fn new_py(sequence: String, midi_dev: String) -> Self {
+        Self::new(sequence, midi_dev)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct GetSequenceQuery {
+    pub sequence: String,
+    // pub midi_dev: String,
+}
+
+impl GetSequenceQuery {
+    pub fn new(sequence: String) -> Self {
+        Self { sequence }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl GetSequenceQuery {
+    #[new]
+    fn new_py(sequence: String) -> Self {
+        Self::new(sequence)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct RenameSequenceBody {
+    pub old_name: String,
+    pub new_name: String,
+}
+
+impl RenameSequenceBody {
+    pub fn new(old_name: String, new_name: String) -> Self {
+        Self { old_name, new_name }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl RenameSequenceBody {
+    #[new]
+    fn new_py(old_name: String, new_name: String) -> Self {
+        Self::new(old_name, new_name)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct SetChannelBody {
+    pub sequence: String,
+    pub channel: MidiChannel,
+}
+
+impl SetChannelBody {
+    pub fn new(sequence: String, channel: MidiChannel) -> Self {
+        Self { sequence, channel }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl SetChannelBody {
+    #[new]
+    fn new_py(sequence: String, channel: MidiChannel) -> Self {
+        Self::new(sequence, channel)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Sequence {
+    pub name: SequenceName,
+    pub steps: Vec<Step>,
+    pub midi_dev: String,
+    pub channel: MidiChannel,
+    /// how long each step lasts.
+    #[serde(default)]
+    pub step_len: NoteDuration,
+    /// how late every other step plays, as a percentage of a step.
+    #[serde(default)]
+    pub swing: u8,
+    /// the timing & velocity feel of the steps.
+    #[serde(default)]
+    pub groove: Option<Groove>,
+}
+
+impl Sequence {
+    pub fn new(name: String) -> Self {
+        let mut seq = Self::default();
+        seq.name = name;
+
+        seq
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self {
+            name: "Default-Sequence".into(),
+            steps: (0..16).map(|_| Vec::default()).collect(),
+            midi_dev: "Midi Through:0".into(),
+            channel: MidiChannel::Ch1,
+            step_len: NoteDuration::Sn(1),
+            swing: 0,
+            groove: None,
+        }
+    }
+}
+
+/// a step of a groove template.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub struct GrooveStep {
+    /// how many pulses late the step plays, (negative to play it early)
+    pub offset: i16,
+    /// the velocity of the step's notes, as a percentage of their own velocity
+    pub velocity: u16,
+}
+
+impl Default for GrooveStep {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            velocity: 100,
+        }
+    }
+}
+
+impl GrooveStep {
+    pub fn new(offset: i16, velocity: u16) -> Self {
+        Self { offset, velocity }
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl GrooveStep {
+    #[new]
+    fn new_py(offset: i16, velocity: u16) -> Self {
+        Self::new(offset, velocity)
+    }
+}
+
+/// a groove template. its steps repeat over the steps of the sequence it's applied to.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Default)]
+pub struct Groove {
+    pub steps: Vec<GrooveStep>,
+}
+
+impl Groove {
+    pub fn new(steps: Vec<GrooveStep>) -> Self {
+        Self { steps }
+    }
+
+    /// the groove step applied to step_i of a sequence.
+    pub fn step(&self, step_i: usize) -> GrooveStep {
+        if self.steps.is_empty() {
+            return GrooveStep::default();
+        }
+
+        self.steps[step_i % self.steps.len()]
+    }
+
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str(json).ok()
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl Groove {
+    #[new]
+    fn new_py(steps: Vec<GrooveStep>) -> Self {
+        Self::new(steps)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_json")]
+    fn from_json_py(json: String) -> Option<Self> {
+        Self::from_json(&json)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct SetSwingBody {
+    pub sequence: String,
+    /// how late every other step plays, as a percentage of a step
+    pub swing: u8,
+}
+
+impl SetSwingBody {
+    pub fn new(sequence: String, swing: u8) -> Self {
+        Self { sequence, swing }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl SetSwingBody {
+    #[new]
+    fn new_py(sequence: String, swing: u8) -> Self {
+        Self::new(sequence, swing)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+/// sets (or with None removes) a sequence's groove template.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct SetGrooveBody {
+    pub sequence: String,
+    pub groove: Option<Groove>,
+}
+
+impl SetGrooveBody {
+    pub fn new(sequence: String, groove: Option<Groove>) -> Self {
+        Self { sequence, groove }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl SetGrooveBody {
+    #[new]
+    fn new_py(sequence: String, groove: Option<Groove>) -> Self {
+        Self::new(sequence, groove)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct SetStepLenBody {
+    pub sequence: String,
+    pub step_len: NoteDuration,
+}
+
+impl SetStepLenBody {
+    pub fn new(sequence: String, step_len: NoteDuration) -> Self {
+        Self { sequence, step_len }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl SetStepLenBody {
+    #[new]
+    fn new_py(sequence: String, step_len: NoteDuration) -> Self {
+        Self::new(sequence, step_len)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct ChangeLenByBody {
+    pub sequence: String,
+    pub amt: isize,
+}
+
+impl ChangeLenByBody {
+    pub fn new(sequence: String, amt: isize) -> Self {
+        Self { sequence, amt }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl ChangeLenByBody {
+    #[new]
+    fn new_py(sequence: String, amt: isize) -> Self {
+        Self::new(sequence, amt)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+/// starts (or replaces) a named automation that runs on the server.
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Debug)]
+pub struct StartAutomationBody {
+    pub name: String,
+    pub conf: AutomationConf,
+    pub midi_dev: String,
+    pub channel: MidiChannel,
+    pub target: AutomationTarget,
+}
+
+impl StartAutomationBody {
+    pub fn new(
+        name: String,
+        conf: AutomationConf,
+        midi_dev: String,
+        channel: MidiChannel,
+        target: AutomationTarget,
+    ) -> Self {
+        Self {
+            name,
+            conf,
+            midi_dev,
+            channel,
+            target,
+        }
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl StartAutomationBody {
+    #[new]
+    fn new_py(
+        name: String,
+        conf: AutomationConf,
+        midi_dev: String,
+        channel: MidiChannel,
+        target: AutomationTarget,
+    ) -> Self {
+        Self::new(name, conf, midi_dev, channel, target)
+    }
+
+    #[pyo3(name = "json")]
+    fn json_py(&self) -> String {
+        self.json()
+    }
+}
+
+/// the version of the project file format this version of midi-daw saves. version 0 was a bare
+/// map of sequences, without a version field.
+pub const PROJECT_VERSION: u32 = 1;
+
+/// everything needed to pick a project back up where it was saved.
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Debug)]
+pub struct Project {
+    /// the file format version, see PROJECT_VERSION
+    pub version: u32,
+    /// None for projects saved before the tempo was
+    #[serde(default)]
+    pub tempo: Option<f64>,
+    /// None for projects saved before the BPQ was
+    #[serde(default)]
+    pub bpq: Option<f64>,
+    /// the virtual midi devices made through the api
+    #[serde(default)]
+    pub virtual_devs: Vec<MidiDeviceName>,
+    pub sequences: Vec<Sequence>,
+    /// the sequences that were playing when the project was saved
+    #[serde(default)]
+    pub playing: Vec<SequenceName>,
+    #[serde(default)]
+    pub routes: Vec<Route>,
+    #[serde(default)]
+    pub scenes: Vec<Scene>,
+    #[serde(default)]
+    pub arrangement: Arrangement,
+    /// the automations that were running when the project was saved
+    #[serde(default)]
+    pub automations: Vec<StartAutomationBody>,
+}
+
+impl Project {
+    /// an empty project, in the current file format.
+    pub fn new() -> Self {
+        Self {
+            version: PROJECT_VERSION,
+            tempo: None,
+            bpq: None,
+            virtual_devs: Vec::default(),
+            sequences: Vec::default(),
+            playing: Vec::default(),
+            routes: Vec::default(),
+            scenes: Vec::default(),
+            arrangement: Arrangement::default(),
+            automations: Vec::default(),
+        }
+    }
+
+    /// reads a project file, migrating it to the current format if it was saved in an older one.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+
+        let Some(version) = value.get("version").and_then(serde_json::Value::as_u64) else {
+            return serde_json::from_value::<BTreeMap<SequenceName, Sequence>>(value).map(
+                |sequences| Self {
+                    sequences: sequences.into_values().collect(),
+                    ..Self::new()
+                },
+            );
+        };
+
+        if version > PROJECT_VERSION as u64 {
+            warn!(
+                "project file is version {version}, newer than {PROJECT_VERSION}, so it may not \
+                 load completely"
+            );
+        }
+
+        let mut project: Self = serde_json::from_value(value)?;
+        project.version = PROJECT_VERSION;
+
+        Ok(project)
+    }
+
+    pub fn json(&self) -> String {
+        let Ok(res) = serde_json::to_string(self) else {
+            return String::new();
+        };
+
+        res
+    }
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// why the sequencer couldn't carry out a command.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[serde(tag = "kind")]
+pub enum SequencerError {
+    /// there's no sequence with this name
+    UnknownSequence {
+        name: SequenceName,
+    },
+    /// there's already a sequence with this name
+    NameTaken {
+        name: SequenceName,
+    },
+    /// there's no scene with this name
+    UnknownScene {
+        name: String,
+    },
+    /// nothing has been saved to disk with this name
+    NotSaved {
+        name: String,
+    },
+    /// the name can't be used as a file name, (eg: it has a "/" in it or starts with a ".")
+    InvalidName {
+        name: String,
+    },
+    /// the step is past the end of the sequence
+    StepOutOfRange {
+        sequence: SequenceName,
+        step: usize,
+        len: usize,
+    },
+    /// a sequence can't be made this long
+    InvalidLen {
+        sequence: SequenceName,
+        len: isize,
+    },
+    /// the arrangement doesn't have a section at this index
+    SectionOutOfRange {
+        index: usize,
+        len: usize,
+    },
+    /// an automation couldn't be made from its config
+    InvalidAutomation {
+        name: String,
+        message: String,
+    },
+    /// the sequence isn't playing
+    NotPlaying {
+        name: SequenceName,
+    },
+    NothingToUndo,
+    NothingToRedo,
+    /// the data directory couldn't be found, ($HOME isn't set)
+    NoDataDir,
+    /// reading or writing a file failed
+    IoError {
+        message: String,
+    },
+    /// data couldn't be (de)serialized
+    ParseError {
+        message: String,
+    },
+}
+
+impl SequencerError {
+    /// true if the command was bad, rather then the server failing to carry it out.
+    pub fn is_client_error(&self) -> bool {
+        !matches!(
+            self,
+            Self::NoDataDir | Self::IoError { .. } | Self::ParseError { .. }
+        )
+    }
+
+    /// true if the command would replace something that already exists.
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, Self::NameTaken { .. })
+    }
+
+    /// true if the command refers to something that doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            Self::UnknownSequence { .. } | Self::UnknownScene { .. } | Self::NotSaved { .. }
+        )
+    }
+}
+
+impl std::fmt::Display for SequencerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownSequence { name } => write!(f, "unknown sequence, \"{name}\""),
+            Self::NameTaken { name } => write!(f, "a sequence named, \"{name}\", already exists"),
+            Self::UnknownScene { name } => write!(f, "unknown scene, \"{name}\""),
+            Self::NotSaved { name } => write!(f, "nothing named, \"{name}\", has been saved"),
+            Self::InvalidName { name } => write!(
+                f,
+                "\"{name}\" can't be saved, names can't be empty, start with a \".\" or contain \
+                 path separators"
+            ),
+            Self::StepOutOfRange {
+                sequence,
+                step,
+                len,
+            } => write!(
+                f,
+                "invalid step, {step}. sequence, \"{sequence}\", only has {len}, steps"
+            ),
+            Self::InvalidLen { sequence, len } => {
+                write!(f, "sequence, \"{sequence}\", can't be {len} steps long")
+            }
+            Self::SectionOutOfRange { index, len } => write!(
+                f,
+                "the arrangement only has {len} sections, can't play section {index}"
+            ),
+            Self::InvalidAutomation { name, message } => {
+                write!(f, "automation, \"{name}\", is invalid. {message}")
+            }
+            Self::NotPlaying { name } => write!(f, "sequence, \"{name}\", is not playing"),
+            Self::NothingToUndo => write!(f, "there's nothing to undo"),
+            Self::NothingToRedo => write!(f, "there's nothing to redo"),
+            Self::NoDataDir => write!(
+                f,
+                "the '$HOME' env var could not be found. so no xdg dir could be set"
+            ),
+            Self::IoError { message } => write!(f, "{message}"),
+            Self::ParseError { message } => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for SequencerError {}
+
+impl From<std::io::Error> for SequencerError {
+    fn from(e: std::io::Error) -> Self {
+        Self::IoError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for SequencerError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::ParseError {
+            message: e.to_string(),
+        }
+    }
+}
+
+/// the json body of an error response.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct ErrorBody {
+    pub error: SequencerError,
+    /// a human readable description of the error
+    pub message: String,
+}
+
+impl From<SequencerError> for ErrorBody {
+    fn from(error: SequencerError) -> Self {
+        Self {
+            message: error.to_string(),
+            error,
+        }
+    }
+}
+
+#[cfg_attr(feature = "pyo3", pyclass)]
+#[derive(Serialize, Deserialize, Encode, Decode, PartialEq, PartialOrd, Clone, Debug)]
+pub enum MsgFromServer {
+    /// a single sync pulse
+    SyncPulse {
+        /// the number of pulses
+        pulse_count: usize,
         /// the last beat to occur
         after_step: usize,
         /// the next beat that will happen
@@ -814,7 +2004,8 @@ pub enum MsgFromServer {
         /// the number of beats in a quarter note
         bpq: f64,
     },
-    /// a step of the sequencer has occured
+    /// a sixteenth note step of the sequencer's clock has occured, (sequences step at their own
+    /// rate, see SequenceStep)
     Step {
         /// the pulse count when the beat happened
         pulse_count: usize,
@@ -832,8 +2023,10 @@ pub enum MsgFromServer {
     SequenceWillStop {
         /// the sequence that will stop
         sequence_name: SequenceName,
-        /// the number of beats it will it stop after
+        /// the number of sixteenth note steps it will it stop after, (rounded up)
         after_steps: usize,
+        /// the number of pulses it will stop after
+        after_pulses: usize,
     },
     /// the described sequence stopped
     SequenceStoped {
@@ -847,14 +2040,87 @@ pub enum MsgFromServer {
     SequenceWillStart {
         /// the sequnece
         sequence_name: SequenceName,
-        /// the number of steps after which this sequence will start
+        /// the number of sixteenth note steps after which this sequence will start, (rounded up)
         after_steps: usize,
+        /// the number of pulses after which this sequence will start
+        after_pulses: usize,
     },
     /// a sequence has started
     SequenceStarted {
         /// the sequnece
         sequence_name: SequenceName,
     },
+    /// a sequence was paused, its playhead is frozen until it is resumed
+    SequencePaused {
+        /// the sequnece
+        sequence_name: SequenceName,
+        /// the step of the sequence that will play when it resumes
+        step_n: usize,
+    },
+    /// a paused sequence has resumed
+    SequenceResumed {
+        /// the sequnece
+        sequence_name: SequenceName,
+        /// the step of the sequence it resumed from
+        step_n: usize,
+    },
+    /// a sequence was armed for recording & will start recording after after_steps
+    RecordingWillStart {
+        /// the sequnece
+        sequence_name: SequenceName,
+        /// the number of steps after which recording will start, (the count-in)
+        after_steps: usize,
+    },
+    /// a sequence started recording
+    RecordingStarted {
+        /// the sequnece
+        sequence_name: SequenceName,
+    },
+    /// a sequence stopped recording
+    RecordingStopped {
+        /// the sequnece
+        sequence_name: SequenceName,
+    },
+    /// a scene was launched, its sequences will start & the rest will stop after after_steps
+    SceneLaunched {
+        /// the scene
+        scene_name: String,
+        /// the number of sixteenth note steps after which the scene takes over, (rounded up)
+        after_steps: usize,
+    },
+    /// a section of the arrangement started playing
+    ArrangementSection {
+        /// the index of the section in the arrangement
+        index: usize,
+    },
+    /// the last section of the arrangement finished, (or the arrangement was stopped)
+    ArrangementEnded(),
+    /// an edit was undone, frontends showing these sequences should refresh them
+    Undone {
+        /// the sequences the undo changed
+        sequence_names: Vec<SequenceName>,
+    },
+    /// an undone edit was redone, frontends showing these sequences should refresh them
+    Redone {
+        /// the sequences the redo changed
+        sequence_names: Vec<SequenceName>,
+    },
+    /// the external midi clock stopped, playback is frozen until it continues or starts again
+    ClockStopped(),
+    /// the external midi clock continued
+    ClockContinued {
+        /// the step it continued from, in sixteenth notes
+        step_n: usize,
+    },
+    /// a playing sequence moved onto one of its steps
+    SequenceStep {
+        /// the sequnece
+        sequence_name: SequenceName,
+        /// the index of the step in the sequence
+        step_n: usize,
+        /// how long the sequence's steps are
+        step_len: NoteDuration,
+    },
 }
 
 impl TryFrom<Vec<u8>> for MsgFromServer {
@@ -974,23 +2240,47 @@ fn midi_daw_types(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<MidiReqBody>()?;
     m.add_class::<AddNoteBody>()?;
     m.add_class::<RmNoteBody>()?;
+    m.add_class::<AddCmdBody>()?;
+    m.add_class::<MidiClockOutBody>()?;
+    m.add_class::<ClockSource>()?;
+    m.add_class::<LaunchQuant>()?;
+    m.add_class::<LaunchBody>()?;
+    m.add_class::<Scene>()?;
+    m.add_class::<LaunchSceneBody>()?;
+    m.add_class::<Section>()?;
+    m.add_class::<Arrangement>()?;
+    m.add_class::<RmCmdBody>()?;
+    m.add_class::<RecordMode>()?;
+    m.add_class::<RecordBody>()?;
+    m.add_class::<MidiMsgKind>()?;
+    m.add_class::<Route>()?;
+    m.add_class::<ImportSmfBody>()?;
     m.add_class::<SetDevBody>()?;
     m.add_class::<GetSequenceQuery>()?;
     m.add_class::<RenameSequenceBody>()?;
     m.add_class::<SetChannelBody>()?;
     m.add_class::<Sequence>()?;
     m.add_class::<ChangeLenByBody>()?;
+    m.add_class::<SetStepLenBody>()?;
+    m.add_class::<SetSwingBody>()?;
+    m.add_class::<SetGrooveBody>()?;
+    m.add_class::<GrooveStep>()?;
+    m.add_class::<Groove>()?;
+    m.add_class::<StartAutomationBody>()?;
     m.add_class::<MsgFromServer>()?;
     // m.add_class::<>()?;
 
     m.add_class::<Automation>()?;
     m.add_class::<AutomationTypes>()?;
     m.add_class::<AutomationConf>()?;
+    m.add_class::<AutomationTarget>()?;
     m.add_class::<LfoConfig>()?;
-    // m.add_class::<LfoConfig>()?;
+    m.add_class::<EnvConfig>()?;
+    m.add_class::<SyncTime>()?;
 
     // m.add_function(wrap_pyfunction!(sum_as_string, m)?)?;
     m.add_function(wrap_pyfunction!(note_from_str, m)?)?;
+    m.add_function(wrap_pyfunction!(sequences_from_smf, m)?)?;
     m.add("UDS_SERVER_PATH", UDS_SERVER_PATH)?;
 
     Ok(())
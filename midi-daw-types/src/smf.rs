@@ -0,0 +1,226 @@
+use crate::{MidiChannel, MidiMsg, NoteDuration, Sequence, Step};
+use midly::{MetaMessage, MidiMessage, Smf, Timing, TrackEventKind};
+use std::collections::{HashMap, HashSet};
+
+/// the steps in a bar of sixteenth notes.
+const BAR_LEN: usize = 16;
+
+/// reads a standard midi file, making a sequence from every track with notes, CCs or pitch bends
+/// in it. everything is quantized to sixteenth note steps & the sequences are whole bars long.
+/// sequences are named after their track, (or "{name}-{track number}" if the track has no name).
+pub fn sequences_from_smf(name: &str, bytes: &[u8]) -> Result<Vec<Sequence>, String> {
+    let smf = Smf::parse(bytes).map_err(|e| format!("parsing midi file failed with error, {e}"))?;
+
+    let Timing::Metrical(ppq) = smf.header.timing else {
+        return Err("midi files timed in SMPTE timecode aren't supported".into());
+    };
+    let ppq = ppq.as_int() as f64;
+    let step_ticks = ppq / 4.;
+
+    let mut sequences: Vec<Sequence> = smf
+        .tracks
+        .iter()
+        .enumerate()
+        .filter_map(|(track_i, track)| {
+            let mut track_name = None;
+            let mut channel = None;
+            // the notes being held, with the tick they started on & their velocity
+            let mut held: HashMap<u8, (u64, u8)> = HashMap::new();
+            // (step, msg) in the order they happened
+            let mut msgs: Vec<(usize, MidiMsg)> = Vec::new();
+            let mut tick = 0;
+            let step_at = |tick: u64| (tick as f64 / step_ticks).round() as usize;
+
+            for event in track {
+                tick += event.delta.as_int() as u64;
+
+                let (ch, message) = match event.kind {
+                    TrackEventKind::Meta(MetaMessage::TrackName(track_name_bytes)) => {
+                        track_name = Some(String::from_utf8_lossy(track_name_bytes).to_string());
+                        continue;
+                    }
+                    TrackEventKind::Midi { channel, message } => (channel.as_int(), message),
+                    _ => continue,
+                };
+
+                channel.get_or_insert(ch);
+
+                match message {
+                    MidiMessage::NoteOn { key, vel } if vel.as_int() > 0 => {
+                        held.insert(key.as_int(), (tick, vel.as_int()));
+                    }
+                    MidiMessage::NoteOn { key, .. } | MidiMessage::NoteOff { key, .. } => {
+                        if let Some((start, velocity)) = held.remove(&key.as_int()) {
+                            msgs.push((
+                                step_at(start),
+                                MidiMsg::PlayNote {
+                                    note: key.as_int(),
+                                    velocity,
+                                    duration: NoteDuration::nearest((tick - start) as f64 / ppq),
+                                },
+                            ));
+                        }
+                    }
+                    MidiMessage::Controller { controller, value } => msgs.push((
+                        step_at(tick),
+                        MidiMsg::CC {
+                            control: controller.as_int(),
+                            value: value.as_int(),
+                        },
+                    )),
+                    MidiMessage::PitchBend { bend } => msgs.push((
+                        step_at(tick),
+                        MidiMsg::PitchBend {
+                            bend: bend.0.as_int(),
+                        },
+                    )),
+                    _ => {}
+                }
+            }
+
+            // notes left hanging end with the track.
+            msgs.extend(held.into_iter().map(|(note, (start, velocity))| {
+                (
+                    step_at(start),
+                    MidiMsg::PlayNote {
+                        note,
+                        velocity,
+                        duration: NoteDuration::nearest((tick - start) as f64 / ppq),
+                    },
+                )
+            }));
+
+            // tracks without any notes or CCs, (ie: the tempo track), aren't sequences.
+            if msgs.is_empty() {
+                return None;
+            }
+
+            let len_steps = (tick as f64 / step_ticks).ceil() as usize;
+            let len = len_steps.div_ceil(BAR_LEN).max(1) * BAR_LEN;
+            let mut steps: Vec<Step> = (0..len).map(|_| Vec::default()).collect();
+
+            for (step_i, msg) in msgs {
+                let step = &mut steps[step_i % len];
+
+                // the last value of a CC on a step wins.
+                if !matches!(msg, MidiMsg::PlayNote { .. }) {
+                    step.retain(|old| !old.same_kind(&msg));
+                }
+
+                step.push(msg);
+            }
+
+            let mut seq = Sequence::new(
+                track_name
+                    .filter(|track_name| !track_name.trim().is_empty())
+                    .unwrap_or(format!("{name}-{track_i}")),
+            );
+            seq.steps = steps;
+            seq.channel = MidiChannel::do_from_hex(format!("{:x}", channel.unwrap_or(0)));
+
+            Some(seq)
+        })
+        .collect();
+
+    // tracks can share a name, sequences can't.
+    let mut names = HashSet::new();
+
+    for (i, seq) in sequences.iter_mut().enumerate() {
+        if !names.insert(seq.name.clone()) {
+            seq.name = format!("{}-{i}", seq.name);
+            names.insert(seq.name.clone());
+        }
+    }
+
+    Ok(sequences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use midly::{
+        Format, Fps, Header, TrackEvent,
+        num::{u4, u7, u15, u28},
+    };
+
+    const PPQ: u16 = 96;
+
+    fn write(timing: Timing, tracks: Vec<Vec<TrackEvent<'static>>>) -> Vec<u8> {
+        let mut smf = Smf::new(Header::new(Format::Parallel, timing));
+        smf.tracks = tracks;
+
+        let mut bytes = Vec::new();
+        smf.write_std(&mut bytes).unwrap();
+
+        bytes
+    }
+
+    fn event(delta: u32, message: MidiMessage) -> TrackEvent<'static> {
+        TrackEvent {
+            delta: u28::new(delta),
+            kind: TrackEventKind::Midi {
+                channel: u4::new(1),
+                message,
+            },
+        }
+    }
+
+    #[test]
+    fn hanging_notes_end_with_the_track() {
+        let track = vec![
+            event(
+                0,
+                MidiMessage::NoteOn {
+                    key: u7::new(60),
+                    vel: u7::new(100),
+                },
+            ),
+            event(
+                PPQ as u32,
+                MidiMessage::Controller {
+                    controller: u7::new(7),
+                    value: u7::new(90),
+                },
+            ),
+        ];
+        let bytes = write(Timing::Metrical(u15::new(PPQ)), vec![track]);
+        let sequences = sequences_from_smf("song", &bytes).unwrap();
+
+        assert_eq!(sequences.len(), 1);
+
+        let seq = &sequences[0];
+
+        assert_eq!(seq.name, "song-0");
+        assert_eq!(seq.channel, MidiChannel::Ch2);
+        assert_eq!(seq.steps.len(), BAR_LEN);
+        assert_eq!(
+            seq.steps[0],
+            vec![MidiMsg::PlayNote {
+                note: 60,
+                velocity: 100,
+                duration: NoteDuration::Qn(1),
+            }]
+        );
+        assert_eq!(
+            seq.steps[4],
+            vec![MidiMsg::CC {
+                control: 7,
+                value: 90,
+            }]
+        );
+    }
+
+    #[test]
+    fn smpte_timing_is_refused() {
+        let track = vec![event(
+            0,
+            MidiMessage::NoteOn {
+                key: u7::new(60),
+                vel: u7::new(100),
+            },
+        )];
+        let bytes = write(Timing::Timecode(Fps::Fps25, 40), vec![track]);
+
+        assert!(sequences_from_smf("song", &bytes).is_err());
+    }
+}
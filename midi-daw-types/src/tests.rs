@@ -0,0 +1,45 @@
+use super::*;
+use serde_json::json;
+
+fn bass() -> Sequence {
+    let mut bass = Sequence::new("bass".into());
+    bass.midi_dev = "synth".into();
+    bass.steps[0] = vec![MidiMsg::CC {
+        control: 74,
+        value: 20,
+    }];
+
+    bass
+}
+
+#[test]
+fn bare_map_projects_load() {
+    let json = json!({ "bass": bass() }).to_string();
+    let project = Project::from_json(&json).unwrap();
+
+    assert_eq!(project.version, PROJECT_VERSION);
+    assert_eq!(project.sequences, vec![bass()]);
+    assert_eq!(project.tempo, None);
+    assert!(project.scenes.is_empty());
+}
+
+#[test]
+fn current_projects_round_trip() {
+    let project = Project {
+        tempo: Some(132.),
+        bpq: Some(48.),
+        virtual_devs: vec!["drums-out".into()],
+        sequences: vec![bass()],
+        playing: vec!["bass".into()],
+        scenes: vec![Scene::new("verse".into(), vec!["bass".into()], None)],
+        ..Project::new()
+    };
+
+    assert_eq!(Project::from_json(&project.json()).unwrap(), project);
+}
+
+#[test]
+fn broken_projects_are_errors() {
+    assert!(Project::from_json("{ \"sequences\": 7 }").is_err());
+    assert!(Project::from_json("not json").is_err());
+}
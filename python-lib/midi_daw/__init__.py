@@ -54,3 +54,19 @@ def tn(n=1):
 
 def s4n(n=1):
     return NoteLen.S4n(n)
+
+
+def qnt(n=1):
+    return NoteLen.QnT(n)
+
+
+def ent(n=1):
+    return NoteLen.EnT(n)
+
+
+def snt(n=1):
+    return NoteLen.SnT(n)
+
+
+def tnt(n=1):
+    return NoteLen.TnT(n)
@@ -10,6 +10,7 @@ By: Calacuda | MIT License | Epoch: Jul 25, 2025
 """
 
 # import asyncio
+import json
 import logging
 import threading
 from copy import copy
@@ -19,9 +20,15 @@ from os import path
 
 import requests
 import requests_unixsocket
-from midi_daw_types import (UDS_SERVER_PATH, Automation, AutomationConf,
-                            LfoConfig, MidiChannel, MidiMsg, MidiReqBody,
-                            MidiTarget, NoteLen, note_from_str)
+from midi_daw_types import (UDS_SERVER_PATH, Arrangement, Automation,
+                            AutomationConf, AutomationTarget, ClockSource,
+                            EnvConfig, Groove, ImportSmfBody, LaunchBody,
+                            LaunchQuant, LaunchSceneBody, LfoConfig,
+                            MidiChannel, MidiClockOutBody, MidiMsg,
+                            MidiMsgKind, MidiReqBody, MidiTarget, NoteLen,
+                            RecordBody, RecordMode, Route, Scene, Section,
+                            SetGrooveBody, SetStepLenBody, SetSwingBody,
+                            StartAutomationBody, SyncTime, note_from_str)
 from thefuzz import process
 from websockets.sync.client import unix_connect
 
@@ -89,6 +96,16 @@ class AutomationWrapper:
         """returns the threads name"""
         return self.name
 
+    def gate_on(self):
+        """starts the automation, (used by envelopes)"""
+        if self.automation is not None:
+            self.automation.gate_on()
+
+    def gate_off(self):
+        """releases the automation, (used by envelopes)"""
+        if self.automation is not None:
+            self.automation.gate_off()
+
 
 def set_log_level(level):
     log.set_level(level)
@@ -140,6 +157,35 @@ def clear_dead_threads():
     }
 
 
+class ServerError(Exception):
+    """
+    raised when the server couldn't carry out a request.
+
+    kind is the type of error, (ie: "UnknownSequence" or "StepOutOfRange"), & details holds the
+    rest of what the server sent back about it. kind is None if the server didn't say.
+    """
+
+    def __init__(self, status_code: int, message: str, kind=None, details=None):
+        super().__init__(message)
+        self.status_code = status_code
+        self.kind = kind
+        self.details = details or {}
+
+
+def _raise_for_error(res):
+    if res.status_code == 200:
+        return
+
+    try:
+        body = res.json()
+        error = body["error"]
+        message = body["message"]
+    except (ValueError, KeyError, TypeError):
+        raise ServerError(res.status_code, res.text or res.reason)
+
+    raise ServerError(res.status_code, message, error.get("kind"), error)
+
+
 def post(data, path):
     socket = UDS_SERVER_PATH.replace("/", "%2F")
     headers = {"Content-Type": "application/json"}
@@ -149,8 +195,7 @@ def post(data, path):
         headers=headers,
     )
 
-    if res.status_code != 200:
-        log.warning(f"{res.text}")
+    _raise_for_error(res)
 
 
 def get(path):
@@ -162,8 +207,7 @@ def get(path):
         # headers=headers,
     )
 
-    if res.status_code != 200:
-        log.warning(f"{res.text}")
+    _raise_for_error(res)
 
     return res.json()
 
@@ -286,6 +330,10 @@ def get_devs() -> list[str]:
     return get("midi")
 
 
+def get_midi_inputs() -> list[str]:
+    return get("midi/inputs")
+
+
 def find_dev(dev_name) -> str:
     """uses a fuzzy finding algo to get the proper device"""
     choices = get_devs()
@@ -339,7 +387,7 @@ def trigger(event: str):
 
 def lfo(
     lfo_type: str,
-    freq: float,  # make this a class or enum that can be per beats, per quarter/eighth/sixteeth/etc note, or based on seconds
+    freq: float | NoteLen | SyncTime,
     # callback: callable,
     one_shot: bool = True,
     bipolar: bool = False,
@@ -350,7 +398,7 @@ def lfo(
     set up a LFO automation
 
     params:
-        freq => the freequency of the lfo oscilation
+        freq => the freequency of the lfo oscilation. a float is in hertz, a NoteLen is one cycle per note length (ie: NoteLen.Wn(2)), or any SyncTime (ie: SyncTime.Steps(8)). note lengths & steps follow the tempo.
         lfo_type => what kind of lfo is this? Options
             - WaveTable => import an LFO from a wav file.
             - sin => sttandard sin wave.
@@ -384,18 +432,26 @@ def lfo(
     lfo_type = lfo_type.lower()
     lfo_types = {
         # "wave": None,
-        "sin": None,
-        "triangle": None,
-        "saw-up": None,
-        "saw-down": None,
-        "antilog": None,
-        "antilog-up": None,
-        "antilog-down": None,
+        "sin": LfoConfig.Sin,
+        "triangle": LfoConfig.Triangle,
+        "saw-up": LfoConfig.SawUp,
+        "saw-down": LfoConfig.SawDown,
+        "anti-log": LfoConfig.AntiLog,
+        "anti-log-up": LfoConfig.AntiLogUp,
+        "anti-log-down": LfoConfig.AntiLogDown,
     }
-    lfo_builder = lfo_types.get(lfo_type)
+    lfo_builder = lfo_types.get(lfo_type.replace("antilog", "anti-log"))
+    freq = _sync_time(freq, SyncTime.Hz)
 
     if lfo_builder is not None:
-        return partial(Automation, automation=lfo_builder)
+        conf = lfo_builder(freq, one_shot, bipolar, hifi_update)
+
+        try:
+            automation = Automation(AutomationConf.Lfo(conf))
+        except ValueError as e:
+            log.error(f"{e}")
+        else:
+            return partial(AutomationWrapper, automation=automation, main_type="lfo")
     elif lfo_type.endswith(".wav") and path.exists(lfo_type):
         # build wave table lfo
         conf = LfoConfig.WaveTable(lfo_type, freq)
@@ -416,17 +472,54 @@ def lfo(
         return None
 
 
+def start_automation(
+    name: str,
+    conf: AutomationConf,
+    target: AutomationTarget,
+    midi_target: MidiTarget = None,
+):
+    """
+    runs an automation on the server, scaled onto a CC or pitch bend target.
+
+    params:
+        name => used to stop the automation later. starting a new automation with the same name replaces the old one.
+        conf => the AutomationConf to build the automation from.
+        target => an AutomationTarget, ie: AutomationTarget.CC(74, 0, 127)
+        midi_target => the device & channel to send to. defaults to the current midi output.
+    """
+    if midi_target is None:
+        midi_target = MIDI_TARGET
+
+    body = StartAutomationBody(name, conf, midi_target.name, midi_target.ch, target)
+    post(body.json(), "automation/start")
+
+
+def stop_automation(name: str):
+    """stops an automation running on the server"""
+    post(json.dumps(name), "automation/stop")
+
+
+def stop_all_automations():
+    """stops every automation running on the server"""
+    post("", "automation/stop-all")
+
+
+def get_automations():
+    """lists the automations running on the server"""
+    return get("automation")
+
+
 # def lfo_off(lfo_name: str):
 #     """turns off an LFO"""
 #     pass
 
 
 def adsr(
-    atk: float,
-    decay: float,
+    atk: float | NoteLen | SyncTime,
+    decay: float | NoteLen | SyncTime,
     sustain: float,
-    release: float,
-    callback: callable,
+    release: float | NoteLen | SyncTime,
+    # callback: callable,
     hifi_update: bool = False,
     # midi_out=midi_out,
 ):
@@ -434,18 +527,64 @@ def adsr(
     set up an ADSR Envelope automation
 
     params:
-        atk: adsr attack
-        decay: adsr deccay
-        sustain: adsr sustain
-        release: adsr release
-        callback: a callback callable that will be called on every update of the adsr
+        atk: adsr attack, in seconds, a NoteLen, or a SyncTime
+        decay: adsr deccay, in seconds, a NoteLen, or a SyncTime
+        sustain: adsr sustain level, between 0.0 & 1.0
+        release: adsr release, in seconds, a NoteLen, or a SyncTime
         hifi_update => should this update on an audio sample rate. if false it updated every beat (24-beat per quarter note by default)
 
-    returns: the name of the adsr, used to stop it
+    use gate_on() & gate_off() on the returned automation to trigger it.
     """
-    # return "ADSR_NAME"
-    adsr = None
-    return partial(Automation, automation=adsr)
+    conf = EnvConfig.ADSR(
+        _sync_time(atk, SyncTime.Secs),
+        _sync_time(decay, SyncTime.Secs),
+        sustain,
+        _sync_time(release, SyncTime.Secs),
+        hifi_update,
+    )
+
+    return _envelope(conf)
+
+
+def ar(
+    atk: float | NoteLen | SyncTime,
+    release: float | NoteLen | SyncTime,
+    hifi_update: bool = False,
+):
+    """
+    set up an Attack Release Envelope automation
+
+    params:
+        atk: attack, in seconds, a NoteLen, or a SyncTime
+        release: release, in seconds, a NoteLen, or a SyncTime
+        hifi_update => should this update on an audio sample rate. if false it updated every beat (24-beat per quarter note by default)
+
+    use gate_on() & gate_off() on the returned automation to trigger it.
+    """
+    conf = EnvConfig.AR(
+        _sync_time(atk, SyncTime.Secs), _sync_time(release, SyncTime.Secs), hifi_update
+    )
+
+    return _envelope(conf)
+
+
+def _sync_time(time, from_float):
+    """converts a float or NoteLen to a SyncTime. floats are wrapped with from_float"""
+    if isinstance(time, SyncTime):
+        return time
+    elif isinstance(time, NoteLen):
+        return SyncTime.Note(time)
+    else:
+        return from_float(float(time))
+
+
+def _envelope(conf: EnvConfig):
+    try:
+        automation = Automation(AutomationConf.EnvelopeGen(conf))
+    except ValueError as e:
+        log.error(f"{e}")
+    else:
+        return partial(AutomationWrapper, automation=automation, main_type="env")
 
 
 # def adsr_off(adsr_name: str):
@@ -459,7 +598,257 @@ def adsr(
 
 def new_dev(dev_name):
     """used to add virtual midi devices"""
-    post(f'"{dev_name}"', "new-dev")
+    post(json.dumps(dev_name), "new-dev")
+
+
+def midi_clock_out(dev_name: str, on: bool = True):
+    """turns midi clock, start/stop & song position output on or off for a midi device"""
+    post(MidiClockOutBody(dev_name, on).json(), "midi/clock-out")
+
+
+def get_midi_clock_outs() -> list[str]:
+    """lists the midi devices with midi clock output turned on"""
+    return get("midi/clock-out")
+
+
+def clock_source(dev_name: str = None):
+    """
+    follow the midi clock, (and start/stop), from the midi input dev_name. if dev_name is None
+    the servers internal clock is used.
+    """
+    if dev_name is None:
+        source = ClockSource.Internal()
+    else:
+        source = ClockSource.External(dev_name)
+
+    post(source.json(), "clock-source")
+
+
+def get_clock_source():
+    """returns where the sequencer gets its sync pulses from"""
+    return get("clock-source")
+
+
+def launch_quant(quant: LaunchQuant):
+    """
+    sets when queued sequences start & stop, (ie: LaunchQuant.Bars(2) to launch on every other
+    bar).
+    """
+    post(quant.json(), "launch-quant")
+
+
+def get_launch_quant():
+    """returns when queued sequences start & stop"""
+    return get("launch-quant")
+
+
+def launch(sequences: list[str], quant: LaunchQuant = None):
+    """starts sequences, quant overrides the launch quantization set with launch_quant"""
+    post(LaunchBody(sequences, quant).json(), "sequence/launch")
+
+
+def launch_stop(sequences: list[str], quant: LaunchQuant = None):
+    """stops sequences, quant overrides the launch quantization set with launch_quant"""
+    post(LaunchBody(sequences, quant).json(), "sequence/launch-stop")
+
+
+def scene(name: str, sequences: list[str], tempo: float = None):
+    """makes (or replaces) a scene, a group of sequences that are launched together"""
+    post(Scene(name, sequences, tempo).json(), "scene")
+
+
+def rm_scene(name: str):
+    post(json.dumps(name), "scene/rm")
+
+
+def get_scenes():
+    """lists the scenes"""
+    return get("scene")
+
+
+def launch_scene(name: str, quant: LaunchQuant = None):
+    """
+    plays a scene's sequences & stops every other sequence. quant overrides the launch
+    quantization set with launch_quant.
+    """
+    post(LaunchSceneBody(name, quant).json(), "scene/launch")
+
+
+def section(sequences: list[str], bars: int, tempo: float = None) -> Section:
+    """a section of an arrangement, sequences play for bars bars"""
+    return Section(sequences, bars, tempo)
+
+
+def arrangement(sections: list[Section], loop_region: tuple[int, int] = None):
+    """
+    sets the arrangement, the sections play one after the other. once the last section of
+    loop_region is reached playback jumps back to its first section.
+    """
+    post(Arrangement(sections, loop_region).json(), "arrangement")
+
+
+def get_arrangement():
+    return get("arrangement")
+
+
+def play_arrangement(from_section: int = 0):
+    """plays through the arrangement, starting from the section at index from_section"""
+    post(f"{from_section}", "arrangement/play")
+
+
+def stop_arrangement():
+    """stops following the arrangement, the sequences playing keep playing"""
+    post("", "arrangement/stop")
+
+
+def record(sequence: str, dev_name: str, mode=RecordMode.Overdub, count_in: int = 1):
+    """
+    records the notes played on the midi input dev_name into sequence. recording starts on the
+    next bar after count_in bars.
+    """
+    post(RecordBody(sequence, dev_name, mode, count_in).json(), "sequence/record")
+
+
+def stop_recording():
+    post("", "sequence/stop-recording")
+
+
+def set_step_len(sequence: str, step_len: NoteLen):
+    """sets how long each step of sequence lasts, (ie: en() to run it in eighth notes)"""
+    post(SetStepLenBody(sequence, step_len).json(), "sequence/set-step-len")
+
+
+def undo():
+    """undoes the last edit to the sequences"""
+    post("", "undo")
+
+
+def redo():
+    """redoes the last undone edit"""
+    post("", "redo")
+
+
+def set_swing(sequence: str, swing: int):
+    """delays every other step of sequence by swing percent of a step"""
+    post(SetSwingBody(sequence, swing).json(), "sequence/set-swing")
+
+
+def set_groove(sequence: str, groove: Groove = None):
+    """applies a groove template to sequence, None removes its groove"""
+    post(SetGrooveBody(sequence, groove).json(), "sequence/set-groove")
+
+
+def load_groove(file_path: str) -> Groove:
+    """
+    reads a groove template from a JSON file, ie:
+    {"steps": [{"offset": 0, "velocity": 100}, {"offset": -2, "velocity": 80}]}
+
+    offsets are in pulses, negative offsets pull a step back from its swing
+    """
+    with open(file_path) as f:
+        groove = Groove.from_json(f.read())
+
+    if groove is None:
+        log.warning(f"{file_path} is not a groove template")
+
+    return groove
+
+
+def _route(from_dev, to_dev, from_channel, to_channel, transpose, drop) -> Route:
+    return Route(
+        from_dev, mk_channel(from_channel), to_dev, mk_channel(to_channel), transpose, drop
+    )
+
+
+def route(
+    from_dev: str,
+    to_dev: str,
+    from_channel=None,
+    to_channel=None,
+    transpose: int = 0,
+    drop: list[MidiMsgKind] = [],
+):
+    """
+    forwards the midi from the input from_dev to the output to_dev.
+
+    params:
+        from_channel => only forward this channel, (None forwards every channel)
+        to_channel => the channel to remap to, (None keeps the channel)
+        transpose => semitones to transpose notes by
+        drop => the kinds of midi message not to forward, (ie: [MidiMsgKind.CC])
+    """
+    post(
+        _route(from_dev, to_dev, from_channel, to_channel, transpose, drop).json(),
+        "midi/route",
+    )
+
+
+def rm_route(
+    from_dev: str,
+    to_dev: str,
+    from_channel=None,
+    to_channel=None,
+    transpose: int = 0,
+    drop: list[MidiMsgKind] = [],
+):
+    """removes a route made with route(...), takes the same arguments"""
+    post(
+        _route(from_dev, to_dev, from_channel, to_channel, transpose, drop).json(),
+        "midi/route/rm",
+    )
+
+
+def get_routes():
+    return get("midi/route")
+
+
+def _export_smf(name: str, path: str, endpoint: str):
+    socket = UDS_SERVER_PATH.replace("/", "%2F")
+    headers = {"Content-Type": "application/json"}
+    res = requests.post(
+        f"http+unix://{socket}/{endpoint}",
+        data=json.dumps(name),
+        headers=headers,
+    )
+
+    _raise_for_error(res)
+
+    with open(path, "wb") as f:
+        f.write(res.content)
+
+
+def export_smf(sequence: str, path: str):
+    """writes a sequence to the standard midi file at path"""
+    _export_smf(sequence, path, "sequence/export-smf")
+
+
+def export_project_smf(project_name: str, path: str):
+    """writes every sequence to the standard midi file at path, one track per sequence"""
+    _export_smf(project_name, path, "project/export-smf")
+
+
+def import_smf(file_path: str, name: str = None) -> list[str]:
+    """
+    makes sequences from the tracks of a standard midi file. tracks without a name are named
+    "{name}-{track number}", name defaults to the file name. returns the new sequences names.
+    """
+    if name is None:
+        name = path.splitext(path.basename(file_path))[0]
+
+    with open(file_path, "rb") as f:
+        smf = f.read()
+
+    socket = UDS_SERVER_PATH.replace("/", "%2F")
+    headers = {"Content-Type": "application/json"}
+    res = requests.post(
+        f"http+unix://{socket}/sequence/import-smf",
+        data=ImportSmfBody(name, list(smf)).json(),
+        headers=headers,
+    )
+
+    _raise_for_error(res)
+
+    return res.json()
 
 
 def play_on(midi_output: str, channel=MidiChannel.Ch1, loop=0, block=False, setup=None):